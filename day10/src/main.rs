@@ -11,8 +11,12 @@ use part_one::part_one;
 mod part_two;
 use part_two::part_two;
 
+mod bitset;
+
 mod topo;
 
+mod trails;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum DayPart {
     One,
@@ -30,6 +34,33 @@ struct Cli {
 
     #[arg(long = "debug", default_value_t = false)]
     debug: bool,
+
+    #[arg(long = "start", default_value_t = 0)]
+    start: u32,
+
+    #[arg(long = "end", default_value_t = 9)]
+    end: u32,
+
+    #[arg(long = "delta", default_value = "1", allow_hyphen_values = true)]
+    delta: topo::DeltaRange,
+
+    #[arg(long = "neighborhood", default_value = "4")]
+    neighborhood: topo::Neighborhood,
+
+    #[arg(long = "trails", default_value_t = false)]
+    trails: bool,
+
+    #[arg(long = "render", default_value_t = false)]
+    render: bool,
+
+    #[arg(long = "trailhead")]
+    trailhead: Option<String>,
+
+    #[arg(long = "summit")]
+    summit: Option<String>,
+
+    #[arg(long = "trail")]
+    trail: Option<usize>,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
@@ -1,8 +1,9 @@
 use std::{
-    collections::HashSet, error, fs, io::{self}
+    error, fs,
+    io::{self},
 };
 
-use crate::topo;
+use crate::{topo, trails};
 
 use crate::Cli;
 
@@ -10,12 +11,17 @@ pub fn part_one(
     args: &Cli,
     file_reader: io::BufReader<fs::File>,
 ) -> Result<(), Box<dyn error::Error>> {
-    let map = topo::Map::from_reader(file_reader)?;
+    let map = topo::Map::from_reader(file_reader, topo::TrailRules::from_cli(args)?)?;
+    let counts = map.trail_counts(args)?;
 
-    // Find paths using a HashSet so we get paths to unique locations.
-    let paths = topo::find_paths::<HashSet<topo::Location>>(args, &map);
+    if args.debug {
+        counts.print_trailheads();
+    }
 
-    println!("{}", paths.len());
+    trails::report(args, &map)?;
+
+    // A trailhead's score is the number of distinct summits it can reach.
+    println!("{}", counts.total_score());
 
     Ok(())
 }
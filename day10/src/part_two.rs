@@ -3,7 +3,7 @@ use std::{
     io::{self},
 };
 
-use crate::topo;
+use crate::{topo, trails};
 
 use crate::Cli;
 
@@ -11,12 +11,17 @@ pub fn part_two(
     args: &Cli,
     file_reader: io::BufReader<fs::File>,
 ) -> Result<(), Box<dyn error::Error>> {
-    let map = topo::Map::from_reader(file_reader)?;
+    let map = topo::Map::from_reader(file_reader, topo::TrailRules::from_cli(args)?)?;
+    let counts = map.trail_counts(args)?;
 
-    // Find paths using a Vec so we get all paths.
-    let paths = topo::find_paths::<Vec<topo::Location>>(args, &map);
+    if args.debug {
+        counts.print_trailheads();
+    }
 
-    println!("{}", paths.len());
+    trails::report(args, &map)?;
+
+    // A trailhead's rating is the number of distinct trails that start there.
+    println!("{}", counts.total_rating());
 
     Ok(())
 }
@@ -1,14 +1,162 @@
-use std::{collections::HashSet, error, io::BufRead};
+use std::{error, io::BufRead, str::FromStr};
+
+use crate::{bitset::Bitset, Cli};
+
+// What counts as a trail: where it starts and ends, and which steps are allowed along the way.
+#[derive(Debug, Clone)]
+pub struct TrailRules {
+    pub start: u32,
+    pub end: u32,
+    pub deltas: DeltaRange,
+    pub neighborhood: Neighborhood,
+}
+
+impl TrailRules {
+    pub fn new(
+        start: u32,
+        end: u32,
+        deltas: DeltaRange,
+        neighborhood: Neighborhood,
+    ) -> Result<TrailRules, Box<dyn error::Error>> {
+        let rules = TrailRules {
+            start,
+            end,
+            deltas,
+            neighborhood,
+        };
+
+        // Every step has to take us closer to the end, otherwise trails could go around in circles forever.
+        let direction = rules.direction();
+        if deltas.min * direction <= 0 || deltas.max * direction <= 0 {
+            return Err(format!(
+                "expected every step in {}..{} to head from {} towards {}",
+                deltas.min, deltas.max, start, end
+            )
+            .into());
+        }
+
+        Ok(rules)
+    }
+
+    pub fn from_cli(args: &Cli) -> Result<TrailRules, Box<dyn error::Error>> {
+        TrailRules::new(args.start, args.end, args.delta, args.neighborhood)
+    }
+
+    fn direction(&self) -> i32 {
+        if self.end >= self.start {
+            1
+        } else {
+            -1
+        }
+    }
+
+    // How many heights away from the start `val` is, as long as it's somewhere between the start and the end.
+    fn rank(&self, val: u32) -> Option<usize> {
+        let rank = (val as i32 - self.start as i32) * self.direction();
+
+        if rank >= 0 && rank <= self.start.abs_diff(self.end) as i32 {
+            Some(rank as usize)
+        } else {
+            None
+        }
+    }
+
+    // The furthest a single step can move us along.
+    fn max_rank_step(&self) -> usize {
+        self.deltas
+            .min
+            .unsigned_abs()
+            .max(self.deltas.max.unsigned_abs()) as usize
+    }
+
+    fn allows(&self, from: u32, to: u32) -> bool {
+        let delta = to as i32 - from as i32;
+
+        delta >= self.deltas.min && delta <= self.deltas.max && self.rank(to).is_some()
+    }
+}
+
+// An inclusive range of height changes a single step can make (e.g. 1, 1..3, -2..-1).
+#[derive(Debug, Clone, Copy)]
+pub struct DeltaRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl FromStr for DeltaRange {
+    type Err = Box<dyn error::Error + Send + Sync>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = match s.split_once("..") {
+            Some((min, max)) => (min.trim().parse()?, max.trim().parse()?),
+            None => {
+                let delta = s.trim().parse()?;
+                (delta, delta)
+            }
+        };
+
+        if min > max {
+            return Err(format!("expected {} to be no bigger than {}", min, max).into());
+        }
+
+        Ok(DeltaRange { min, max })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Neighborhood {
+    // Up, down, left and right.
+    #[default]
+    Four,
+
+    // Diagonals, too.
+    Eight,
+}
+
+impl Neighborhood {
+    fn offsets(&self) -> &'static [(i32, i32)] {
+        match self {
+            Neighborhood::Four => &[(0, 1), (1, 0), (0, -1), (-1, 0)],
+            Neighborhood::Eight => &[
+                (0, 1),
+                (1, 1),
+                (1, 0),
+                (1, -1),
+                (0, -1),
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+            ],
+        }
+    }
+}
+
+impl FromStr for Neighborhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" => Ok(Neighborhood::Four),
+            "8" => Ok(Neighborhood::Eight),
+            _ => Err(format!("expected neighborhood to be 4 or 8, got {}", s)),
+        }
+    }
+}
 
 pub struct Map {
-    map: Vec<Vec<u32>>,
+    // Impassable locations don't have a height.
+    map: Vec<Vec<Option<u32>>>,
+    rules: TrailRules,
     trailheads: Vec<Location>,
 }
 
 pub type Location = (usize, usize);
 
 impl Map {
-    pub fn from_reader<R: BufRead>(reader: R) -> Result<Map, Box<dyn error::Error>> {
+    pub fn from_reader<R: BufRead>(
+        reader: R,
+        rules: TrailRules,
+    ) -> Result<Map, Box<dyn error::Error>> {
         let mut map = vec![];
         let mut trailheads = vec![];
 
@@ -17,28 +165,45 @@ impl Map {
 
             let mut row = vec![];
             for (x, ch) in line.chars().enumerate() {
+                if ch == '.' {
+                    row.push(None);
+                    continue;
+                }
+
                 let val = ch
                     .to_digit(10)
-                    .expect(format!("expected u32 at ({}, {})", x, y).as_str());
+                    .ok_or_else(|| format!("expected u32 or . at ({}, {})", x, y))?;
 
                 // If this is a trailhead, remember it for later.
-                if val == 0 {
+                if val == rules.start {
                     trailheads.push((x, y))
                 }
 
-                row.push(val);
+                row.push(Some(val));
             }
 
             map.push(row);
         }
 
-        Ok(Map { map, trailheads })
+        Ok(Map {
+            map,
+            rules,
+            trailheads,
+        })
     }
 
     pub fn trailheads(&self) -> &[Location] {
         &self.trailheads
     }
 
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.map.first().map_or(0, |row| row.len()), self.map.len())
+    }
+
+    pub fn is_summit(&self, loc: &Location) -> bool {
+        self.map[loc.1][loc.0] == Some(self.rules.end)
+    }
+
     pub fn next_steps(
         &self,
         loc: &Location,
@@ -50,17 +215,12 @@ impl Map {
 
         let loc = (loc.0 as i32, loc.1 as i32);
 
-        let possible_steps = [
-            (loc.0, loc.1 + 1),
-            (loc.0 + 1, loc.1),
-            (loc.0, loc.1 - 1),
-            (loc.0 - 1, loc.1),
-        ];
-
         let mut steps = vec![];
-        for step in possible_steps {
+        for offset in self.rules.neighborhood.offsets() {
+            let step = (loc.0 + offset.0, loc.1 + offset.1);
+
             match self.try_get(&step) {
-                Some(step_val) if step_val == loc_val + 1 => {
+                Some(step_val) if self.rules.allows(loc_val, step_val) => {
                     steps.push(((step.0 as usize, step.1 as usize), step_val));
                 }
                 _ => {}
@@ -79,100 +239,141 @@ impl Map {
         self.map
             .get(loc.1)
             .and_then(|row| row.get(loc.0))
-            .map(|val| *val)
+            .copied()
+            .flatten()
     }
-}
 
-pub fn find_paths<C: MutCollection<Location>>(args: &crate::Cli, map: &Map) -> C {
-    let mut paths = C::new();
+    // Figures out the score and rating of every location on the map in a single pass.
+    //
+    // Every step takes a trail closer to the end, so we can work our way back from the summits: a location's
+    // reachable summits are the union of its next steps' summits, and its number of trails is the sum of theirs.
+    pub fn trail_counts(&self, args: &crate::Cli) -> Result<TrailCounts, Box<dyn error::Error>> {
+        let (width, height) = self.dimensions();
+
+        // Bucket locations by how far along a trail they'd be so we can visit each level all at once.
+        let summit_level = self.rules.start.abs_diff(self.rules.end) as usize;
+
+        let mut levels = vec![vec![]; summit_level + 1];
+        for (y, row) in self.map.iter().enumerate() {
+            for (x, val) in row.iter().enumerate() {
+                if let Some(level) = val.and_then(|val| self.rules.rank(val)) {
+                    levels[level].push((x, y));
+                }
+            }
+        }
 
-    // for each trailhead:
-    for (trailhead_i, trailhead) in map.trailheads().iter().enumerate() {
-        // find all paths for this trailhead!
-        let mut frontier = vec![vec![*trailhead]];
+        let summits = levels[summit_level].clone();
 
-        let mut trailhead_paths = C::new();
-        while let Some(path) = frontier.pop() {
-            let loc = path.last().unwrap();
+        let mut scores = vec![vec![0; width]; height];
+        let mut ratings = vec![vec![0; width]; height];
 
-            if args.debug {
-                println!(
-                    "path: {:?} ({})",
-                    path,
-                    map.try_get(&(loc.0 as i32, loc.1 as i32)).unwrap()
-                );
-            }
+        // We only ever need the summit sets for the levels a step away from the one we're on, so just keep those
+        // around.
+        let mut summit_sets = vec![vec![None; width]; height];
 
-            // find all moves at this location:
-            for (step_loc, val) in map.next_steps(loc).unwrap() {
-                if args.debug {
-                    println!("step: {:?} ({})", step_loc, val);
-                }
+        for (i, (x, y)) in summits.iter().enumerate() {
+            let mut set = Bitset::new(summits.len());
+            set.insert(i);
 
-                // if the val is 9, we found a complete path!
-                if val == 9 {
-                    if args.debug {
-                        println!("found path!")
+            summit_sets[*y][*x] = Some(set);
+            scores[*y][*x] = 1;
+            ratings[*y][*x] = 1;
+        }
+
+        for level in (0..summit_level).rev() {
+            for loc in &levels[level] {
+                let mut set = Bitset::new(summits.len());
+                let mut rating = 0;
+
+                for (step, _) in self.next_steps(loc)? {
+                    if let Some(step_set) = &summit_sets[step.1][step.0] {
+                        set.union_with(step_set);
                     }
 
-                    trailhead_paths.add(step_loc);
-                    continue;
+                    rating += ratings[step.1][step.0];
                 }
 
-                // otherwise, add the new path to the frontier and we'll check it out later!
-                let mut new_path = path.clone();
-                new_path.extend_from_slice(&[step_loc]);
-                frontier.push(new_path);
+                scores[loc.1][loc.0] = set.len();
+                ratings[loc.1][loc.0] = rating;
+                summit_sets[loc.1][loc.0] = Some(set);
             }
-        }
 
-        if args.debug {
-            println!(
-                "trailhead {} had {} paths",
-                trailhead_i,
-                trailhead_paths.len()
-            );
+            // Nothing left can step as far as this, so it's done with now.
+            for (x, y) in levels
+                .get(level + self.rules.max_rank_step())
+                .into_iter()
+                .flatten()
+            {
+                summit_sets[*y][*x] = None;
+            }
+
+            if args.debug {
+                println!("level {}: {} locations", level, levels[level].len());
+            }
         }
 
-        paths.extend(trailhead_paths);
+        Ok(TrailCounts {
+            trailheads: self.trailheads().to_vec(),
+            summits,
+            summit_sets: self
+                .trailheads
+                .iter()
+                .map(|(x, y)| summit_sets[*y][*x].take().unwrap_or_else(|| Bitset::new(0)))
+                .collect(),
+            scores,
+            ratings,
+        })
     }
-
-    paths
 }
 
-pub trait MutCollection<T>: Extend<T> + IntoIterator<Item = T> {
-    fn add(&mut self, item: T);
-    fn new() -> Self;
-    fn len(&self) -> usize;
+pub struct TrailCounts {
+    trailheads: Vec<Location>,
+    summits: Vec<Location>,
+
+    // The summits reachable from each trailhead (as indexes into `summits`).
+    summit_sets: Vec<Bitset>,
+
+    // The number of distinct summits reachable from each location.
+    scores: Vec<Vec<usize>>,
+
+    // The number of distinct trails from each location to any summit.
+    ratings: Vec<Vec<usize>>,
 }
 
-impl<T> MutCollection<T> for Vec<T> {
-    fn add(&mut self, item: T) {
-        self.push(item);
+impl TrailCounts {
+    pub fn score(&self, loc: &Location) -> usize {
+        self.scores[loc.1][loc.0]
     }
 
-    fn new() -> Self {
-        Vec::new()
+    pub fn rating(&self, loc: &Location) -> usize {
+        self.ratings[loc.1][loc.0]
     }
 
-    fn len(&self) -> usize {
-        self.len()
+    pub fn reachable_summits(&self, trailhead: usize) -> Vec<Location> {
+        self.summit_sets[trailhead]
+            .iter()
+            .map(|i| self.summits[i])
+            .collect()
     }
-}
 
-impl<T> MutCollection<T> for HashSet<T>
-where
-    T: Eq + std::hash::Hash,
-{
-    fn add(&mut self, item: T) {
-        self.insert(item);
+    pub fn total_score(&self) -> usize {
+        self.trailheads.iter().map(|loc| self.score(loc)).sum()
     }
 
-    fn new() -> Self {
-        Self::new()
+    pub fn total_rating(&self) -> usize {
+        self.trailheads.iter().map(|loc| self.rating(loc)).sum()
     }
 
-    fn len(&self) -> usize {
-        self.len()
+    pub fn print_trailheads(&self) {
+        for (i, loc) in self.trailheads.iter().enumerate() {
+            println!(
+                "trailhead {} at {:?}: score {}, rating {}, summits {:?}",
+                i,
+                loc,
+                self.score(loc),
+                self.rating(loc),
+                self.reachable_summits(i)
+            );
+        }
     }
 }
@@ -0,0 +1,108 @@
+use std::{collections::HashSet, error};
+
+use crate::{
+    topo::{Location, Map},
+    Cli,
+};
+
+// Walks every complete trail (from a trailhead all the way up to a summit) one at a time.
+pub fn trails<'a>(
+    map: &'a Map,
+    trailhead: Option<Location>,
+    summit: Option<Location>,
+) -> impl Iterator<Item = Vec<Location>> + 'a {
+    map.trailheads()
+        .iter()
+        .filter(move |loc| trailhead.is_none_or(|trailhead| **loc == trailhead))
+        .flat_map(move |trailhead| {
+            search::paths(
+                *trailhead,
+                |loc| {
+                    // `next_steps` only fails for locations that are off the map or impassable, and we only ever
+                    // walk from trailheads and the steps `next_steps` hands back, which are always neither.
+                    map.next_steps(loc)
+                        .expect("trails should only visit passable locations on the map")
+                        .into_iter()
+                        .map(|(loc, _)| loc)
+                        .collect::<Vec<_>>()
+                },
+                |loc| map.is_summit(loc),
+            )
+        })
+        .filter(move |trail| summit.is_none_or(|summit| trail.last() == Some(&summit)))
+}
+
+// Prints whichever trails were asked for (and draws them, if we're rendering).
+pub fn report(args: &Cli, map: &Map) -> Result<(), Box<dyn error::Error>> {
+    if !args.trails && !args.render {
+        return Ok(());
+    }
+
+    let trailhead = args.trailhead.as_deref().map(parse_location).transpose()?;
+    let summit = args.summit.as_deref().map(parse_location).transpose()?;
+
+    let mut trails = trails(map, trailhead, summit).collect::<Vec<_>>();
+
+    // If we only want one trail, throw out the rest.
+    if let Some(n) = args.trail {
+        if n >= trails.len() {
+            return Err(format!(
+                "expected trail {} to exist (found {} trails)",
+                n,
+                trails.len()
+            )
+            .into());
+        }
+
+        trails = vec![trails.swap_remove(n)];
+    }
+
+    if args.trails {
+        for trail in &trails {
+            println!(
+                "{}",
+                trail
+                    .iter()
+                    .map(|(x, y)| format!("{},{}", x, y))
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            );
+        }
+
+        println!();
+    }
+
+    if args.render {
+        println!("{}", render(map, &trails));
+    }
+
+    Ok(())
+}
+
+// Draws the map with only the locations that are on one of the trails filled in.
+pub fn render(map: &Map, trails: &[Vec<Location>]) -> String {
+    let on_trail = trails.iter().flatten().collect::<HashSet<_>>();
+    let (width, height) = map.dimensions();
+
+    let mut out = String::new();
+    for y in 0..height {
+        for x in 0..width {
+            match map.try_get(&(x as i32, y as i32)) {
+                Some(val) if on_trail.contains(&(x, y)) => out.push_str(&val.to_string()),
+                _ => out.push('.'),
+            }
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+fn parse_location(loc: &str) -> Result<Location, Box<dyn error::Error>> {
+    let (x, y) = loc
+        .split_once(',')
+        .ok_or("expected location in the form x,y")?;
+
+    Ok((x.trim().parse()?, y.trim().parse()?))
+}
@@ -0,0 +1,125 @@
+use std::{error, fs, io};
+
+use crate::{rules::Rules, stones::Stones, Cli};
+
+pub fn run(
+    args: &Cli,
+    file_reader: io::BufReader<fs::File>,
+    default_blinks: usize,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut stones = Stones::from_reader(file_reader, Rules::from_cli(args)?);
+    let blinks = args.blinks.unwrap_or(default_blinks);
+
+    if args.debug {
+        println!("{:?}", stones.distinct());
+    }
+
+    if args.stats {
+        println!("blink  distinct  cache  hit rate  stones");
+    }
+
+    // Once a blink doesn't turn up any stone we haven't seen before, it never will again (every stone it could
+    // turn into has already been seen), so the stones only ever take on the same handful of values from then on.
+    let mut converged_at = None;
+    let mut prev_log10 = stones.count().log10();
+    let mut growth_log10 = 0.0;
+
+    for blink in 1..=blinks {
+        let stats = stones.blink()?;
+
+        // Only used for showing how fast the stones are growing, so an estimate is fine.
+        let log10 = stones.count().log10();
+        growth_log10 = log10 - prev_log10;
+        prev_log10 = log10;
+
+        if args.debug {
+            println!("{:?}", stones.distinct());
+        }
+
+        if args.stats {
+            println!(
+                "{:>5}  {:>8}  {:>5}  {:>7.2}%  {}",
+                blink,
+                stats.distinct,
+                stats.cache_size,
+                stats.hit_rate() * 100.0,
+                stones.count()
+            );
+        }
+
+        if stats.misses == 0 && converged_at.is_none() {
+            converged_at = Some(blink);
+
+            if args.stats {
+                println!("(converged: no new values after this blink)");
+            }
+        }
+    }
+
+    if args.stats {
+        println!();
+    }
+
+    // After converging, the counts for each value are a fixed linear function of the last blink's, so we can
+    // skip straight to the answer (the total also ends up growing by roughly the same factor every blink).
+    if let Some(target) = args.extrapolate {
+        let converged_at = converged_at.ok_or(format!(
+            "expected stones to converge within {} blinks (try more --blinks)",
+            blinks
+        ))?;
+
+        if target < blinks {
+            return Err(format!("expected to extrapolate past {} blinks", blinks).into());
+        }
+
+        println!(
+            "converged at blink {}, growing by ~{:.6}x per blink",
+            converged_at,
+            10f64.powf(growth_log10)
+        );
+
+        println!(
+            "{} stones after {} blinks",
+            stones.extrapolate(target - blinks)?,
+            target
+        );
+
+        println!();
+    }
+
+    if let Some(stone) = args.descendants {
+        if !stones.initial().contains(&stone) {
+            return Err(format!("expected {} to be one of the initial stones", stone).into());
+        }
+
+        println!(
+            "{} becomes {} stones after {} blinks",
+            stone,
+            stones.descendants(stone, blinks)?,
+            blinks
+        );
+
+        println!();
+    }
+
+    if let Some(value) = args.produces {
+        for produced in stones.first_produced(value, blinks)? {
+            match produced.blink {
+                Some(blink) => println!(
+                    "{} first produces {} on blink {}",
+                    produced.stone, value, blink
+                ),
+                None => println!(
+                    "{} never produces {} within {} blinks",
+                    produced.stone, value, blinks
+                ),
+            }
+        }
+
+        println!();
+    }
+
+    println!("{}", stones.count());
+
+    Ok(())
+}
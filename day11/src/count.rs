@@ -0,0 +1,112 @@
+use std::{fmt, ops::AddAssign};
+
+// A count of stones that can get as big as it needs to.
+//
+// Stones roughly multiply by 1.5 every blink, so even a u128 runs out after a couple hundred blinks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Count {
+    // Base 2^32 digits, least significant first (and never with any zeros on the end).
+    limbs: Vec<u32>,
+}
+
+impl Count {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // Close enough for estimating; only the top couple of limbs actually matter.
+    pub fn log10(&self) -> f64 {
+        let top = self
+            .limbs
+            .iter()
+            .rev()
+            .take(2)
+            .fold(0f64, |acc, limb| acc * 2f64.powi(32) + *limb as f64);
+
+        let shift = self.limbs.len().saturating_sub(2) * 32;
+
+        top.log10() + shift as f64 * 2f64.log10()
+    }
+
+    // Divides by `divisor` in place, returning the remainder.
+    fn div_rem(&mut self, divisor: u32) -> u32 {
+        let mut rem = 0u64;
+
+        for limb in self.limbs.iter_mut().rev() {
+            let curr = (rem << 32) | *limb as u64;
+
+            *limb = (curr / divisor as u64) as u32;
+            rem = curr % divisor as u64;
+        }
+
+        self.trim();
+
+        rem as u32
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl From<u64> for Count {
+    fn from(n: u64) -> Self {
+        let mut count = Count {
+            limbs: vec![n as u32, (n >> 32) as u32],
+        };
+
+        count.trim();
+        count
+    }
+}
+
+impl AddAssign<&Count> for Count {
+    fn add_assign(&mut self, other: &Count) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+
+        let mut carry = 0u64;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + other.limbs.get(i).copied().unwrap_or(0) as u64 + carry;
+
+            *limb = sum as u32;
+            carry = sum >> 32;
+
+            // Once we're past the other count, there's nothing left to do if there's nothing to carry.
+            if carry == 0 && i >= other.limbs.len() {
+                break;
+            }
+        }
+
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.write_str("0");
+        }
+
+        // Peel off 9 decimal digits at a time (the most that fit in a u32), starting from the right.
+        let mut rest = self.clone();
+        let mut chunks = vec![];
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem(1_000_000_000));
+        }
+
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+
+        Ok(())
+    }
+}
@@ -11,6 +11,12 @@ use part_one::part_one;
 mod part_two;
 use part_two::part_two;
 
+mod blinks;
+
+mod count;
+
+mod rules;
+
 mod stones;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -30,6 +36,24 @@ struct Cli {
 
     #[arg(long = "debug", default_value_t = false)]
     debug: bool,
+
+    #[arg(long = "rules")]
+    rules: Option<String>,
+
+    #[arg(long = "blinks")]
+    blinks: Option<usize>,
+
+    #[arg(long = "stats", default_value_t = false)]
+    stats: bool,
+
+    #[arg(long = "extrapolate")]
+    extrapolate: Option<usize>,
+
+    #[arg(long = "descendants")]
+    descendants: Option<u64>,
+
+    #[arg(long = "produces")]
+    produces: Option<u64>,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
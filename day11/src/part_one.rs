@@ -3,29 +3,11 @@ use std::{
     io::{self},
 };
 
-use crate::stones;
-
-use crate::Cli;
+use crate::{blinks, Cli};
 
 pub fn part_one(
     args: &Cli,
     file_reader: io::BufReader<fs::File>,
 ) -> Result<(), Box<dyn error::Error>> {
-    let mut stones_list = stones::Stones::from_reader(file_reader);
-
-    if args.debug {
-        println!("{:?}", stones_list.distinct());
-    }
-
-    for _ in 0..6 {
-        stones_list.blink();
-
-        if args.debug {
-            println!("{:?}", stones_list.distinct());
-        }
-    }
-
-    println!("{}", stones_list.count());
-
-    Ok(())
+    blinks::run(args, file_reader, 25)
 }
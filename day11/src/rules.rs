@@ -0,0 +1,190 @@
+use std::{error, fs, str::FromStr};
+
+use crate::Cli;
+
+// The rules from the puzzle, in the same format as a rules file.
+const DEFAULT_RULES: &str = "
+equals 0 => replace 1
+digits even => split
+always => multiply 2024
+";
+
+// What happens to stones when we blink.
+//
+// Rules are written one per line as `<predicate> => <action>` and are checked in order; the first rule whose
+// predicate matches a stone decides what the stone turns into. Blank lines and lines starting with `#` are
+// ignored.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub predicate: Predicate,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Predicate {
+    // equals <n>
+    Equals(u64),
+
+    // digits even
+    EvenDigits,
+
+    // digits odd
+    OddDigits,
+
+    // divisible <n>
+    DivisibleBy(u64),
+
+    // always
+    Always,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    // replace <n>
+    Replace(u64),
+
+    // split (the left half gets the extra digit if there's an odd number of them)
+    Split,
+
+    // multiply <n>
+    Multiply(u64),
+
+    // add <n>
+    Add(u64),
+}
+
+impl Rules {
+    pub fn from_cli(args: &Cli) -> Result<Rules, Box<dyn error::Error>> {
+        match &args.rules {
+            Some(path) => fs::read_to_string(path)?.parse(),
+            None => DEFAULT_RULES.parse(),
+        }
+    }
+
+    // Figures out what a stone turns into (a stone that doesn't match any rule stays the same).
+    pub fn apply(&self, stone: u64) -> Result<(u64, Option<u64>), Box<dyn error::Error>> {
+        let rule = self.rules.iter().find(|rule| rule.predicate.matches(stone));
+
+        let split = match rule.map(|rule| rule.action) {
+            None => (stone, None),
+            Some(Action::Replace(n)) => (n, None),
+            Some(Action::Split) => {
+                let (left, right) = split_num(stone, num_digits(stone));
+
+                (left, Some(right))
+            }
+            Some(Action::Multiply(n)) => (
+                stone
+                    .checked_mul(n)
+                    .ok_or_else(|| format!("{} * {} overflowed", stone, n))?,
+                None,
+            ),
+            Some(Action::Add(n)) => (
+                stone
+                    .checked_add(n)
+                    .ok_or_else(|| format!("{} + {} overflowed", stone, n))?,
+                None,
+            ),
+        };
+
+        Ok(split)
+    }
+}
+
+impl FromStr for Rules {
+    type Err = Box<dyn error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = vec![];
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (predicate, action) = line.split_once("=>").ok_or_else(|| {
+                format!(
+                    "expected rule in the form <predicate> => <action>: {}",
+                    line
+                )
+            })?;
+
+            rules.push(Rule {
+                predicate: predicate.parse()?,
+                action: action.parse()?,
+            });
+        }
+
+        Ok(Rules { rules })
+    }
+}
+
+impl Predicate {
+    pub fn matches(&self, stone: u64) -> bool {
+        match self {
+            Predicate::Equals(n) => stone == *n,
+            Predicate::EvenDigits => num_digits(stone).is_multiple_of(2),
+            Predicate::OddDigits => !num_digits(stone).is_multiple_of(2),
+            Predicate::DivisibleBy(n) => stone.is_multiple_of(*n),
+            Predicate::Always => true,
+        }
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = Box<dyn error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["equals", n] => Ok(Predicate::Equals(n.parse()?)),
+            ["digits", "even"] => Ok(Predicate::EvenDigits),
+            ["digits", "odd"] => Ok(Predicate::OddDigits),
+            ["divisible", n] => Ok(Predicate::DivisibleBy(n.parse()?)),
+            ["always"] => Ok(Predicate::Always),
+            _ => Err(format!("unknown predicate: {}", s.trim()).into()),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = Box<dyn error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            ["replace", n] => Ok(Action::Replace(n.parse()?)),
+            ["split"] => Ok(Action::Split),
+            ["multiply", n] => Ok(Action::Multiply(n.parse()?)),
+            ["add", n] => Ok(Action::Add(n.parse()?)),
+            _ => Err(format!("unknown action: {}", s.trim()).into()),
+        }
+    }
+}
+
+// 0 has one digit, even though there's nothing left of it after dividing by 10.
+fn num_digits(num: u64) -> usize {
+    let mut res = 1;
+
+    let mut curr = num / 10;
+    while curr > 0 {
+        curr /= 10;
+        res += 1;
+    }
+
+    res
+}
+
+fn split_num(num: u64, digits: usize) -> (u64, u64) {
+    // Get the left divisor.
+    // e.g. num=5214; digits=4; 10^(4/2) = 100; left = 5214 / 100 = 52; right = 5214 % 100 = 14.
+    let left_divisor = 10u64.pow((digits / 2) as u32);
+
+    let left = num / left_divisor;
+    let right = num % left_divisor;
+
+    (left, right)
+}
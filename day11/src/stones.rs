@@ -1,46 +1,84 @@
 use std::{
     collections::{HashMap, HashSet},
+    error,
     io::BufRead,
     mem,
 };
 
-use maplit::hashmap;
+use crate::{count::Count, rules::Rules};
 
 pub struct Stones {
     stones: HashSet<u64>,
 
-    curr_blink_counts: HashMap<u64, u64>,
+    curr_blink_counts: HashMap<u64, Count>,
     splits: HashMap<u64, (u64, Option<u64>)>,
+    rules: Rules,
+
+    initial: Vec<u64>,
+    blinks: usize,
+
+    // How often the last blink already knew how a stone would split.
+    hits: usize,
+    misses: usize,
+}
+
+// What happened during a single blink.
+#[derive(Debug)]
+pub struct BlinkStats {
+    pub distinct: usize,
+    pub cache_size: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl BlinkStats {
+    pub fn hit_rate(&self) -> f64 {
+        if self.hits + self.misses == 0 {
+            return 0.0;
+        }
+
+        self.hits as f64 / (self.hits + self.misses) as f64
+    }
+}
+
+// When an initial stone first turned into some value (if it ever did).
+#[derive(Debug)]
+pub struct FirstProduced {
+    pub stone: u64,
+    pub blink: Option<usize>,
 }
 
 impl Stones {
-    pub fn from_reader<R: BufRead>(reader: R) -> Self {
+    pub fn from_reader<R: BufRead>(reader: R, rules: Rules) -> Self {
         let mut stones = HashSet::new();
         let mut counts = HashMap::new();
+        let mut initial = vec![];
 
         let line = reader.lines().next().unwrap().unwrap();
         for stone in line.split(" ").map(|ch| ch.parse::<u64>().unwrap()) {
-            counts.insert(
-                stone,
-                match counts.get(&stone) {
-                    Some(count) => count + 1,
-                    None => 1,
-                },
-            );
+            *counts.entry(stone).or_default() += &Count::from(1);
 
             stones.insert(stone);
+            initial.push(stone);
         }
 
         Stones {
             stones,
             curr_blink_counts: counts,
-            splits: hashmap! {
-                0 => (1, None),
-            },
+            splits: HashMap::new(),
+            rules,
+            initial,
+            blinks: 0,
+            hits: 0,
+            misses: 0,
         }
     }
 
-    pub fn blink(&mut self) {
+    pub fn blink(&mut self) -> Result<BlinkStats, Box<dyn error::Error>> {
+        self.blinks += 1;
+        self.hits = 0;
+        self.misses = 0;
+
         // Swap out the existing set of stones for a new empty one with the same capacity.
         let mut stones = HashSet::with_capacity(self.stones.len());
         mem::swap(&mut stones, &mut self.stones);
@@ -53,10 +91,9 @@ impl Stones {
             // Get the number of stones with this value from last blink.
             let stone_count = last_blink_counts
                 .get(&stone)
-                .expect("a stone from last blink should have a count")
-                .to_owned();
+                .expect("a stone from last blink should have a count");
 
-            let (left, right) = self.split(stone);
+            let (left, right) = self.split(stone, self.blinks)?;
 
             // Add produced stone n-many times since each parent would have _also_ produced these stones.
             self.add_stone(left, stone_count);
@@ -64,53 +101,190 @@ impl Stones {
                 self.add_stone(right, stone_count);
             }
         }
+
+        Ok(BlinkStats {
+            distinct: self.stones.len(),
+            cache_size: self.splits.len(),
+            hits: self.hits,
+            misses: self.misses,
+        })
     }
 
     #[inline]
-    fn add_stone(&mut self, stone: u64, count: u64) {
+    fn add_stone(&mut self, stone: u64, count: &Count) {
         // Add the stone.
         self.stones.insert(stone);
 
         // Save the total count of this stone value for this blink.
-        let count = match self.curr_blink_counts.get(&stone) {
-            Some(curr) => curr + count,
-            None => count,
-        };
-
-        self.curr_blink_counts.insert(stone, count);
+        *self.curr_blink_counts.entry(stone).or_default() += count;
     }
 
-    fn split(&mut self, stone: u64) -> (u64, Option<u64>) {
+    fn split(
+        &mut self,
+        stone: u64,
+        blink: usize,
+    ) -> Result<(u64, Option<u64>), Box<dyn error::Error>> {
         // Check if we already know how this stone is going to split...
         if let Some(split) = self.splits.get(&stone) {
-            return *split;
+            self.hits += 1;
+            return Ok(*split);
         }
 
+        self.misses += 1;
+
         // ...otherwise, let's find out!
-        let split = {
-            let num_digits = num_digits(stone);
+        let split = self.rules.apply(stone).map_err(|err| {
+            format!(
+                "stone {} couldn't change on blink {}: {}",
+                stone, blink, err
+            )
+        })?;
 
-            // If the number of digits is even, split the number...
-            if num_digits % 2 == 0 {
-                let (left, right) = split_num(stone, num_digits);
+        // Record how this stone should split.
+        self.splits.insert(stone, split);
+
+        Ok(split)
+    }
+
+    pub fn initial(&self) -> &[u64] {
+        &self.initial
+    }
+
+    // Counts how many stones a single stone turns into after some number of blinks (regardless of how many times
+    // we've actually blinked).
+    pub fn descendants(
+        &mut self,
+        stone: u64,
+        blinks: usize,
+    ) -> Result<Count, Box<dyn error::Error>> {
+        let mut counts = HashMap::from([(stone, Count::from(1))]);
+
+        for blink in 1..=blinks {
+            let mut next_counts: HashMap<u64, Count> = HashMap::with_capacity(counts.len());
 
-                (left, Some(right))
-            } else {
-                // ...otherwise, just multiply by 2024
-                (stone * 2024, None)
+            for (stone, count) in counts {
+                let (left, right) = self.split(stone, blink)?;
+
+                *next_counts.entry(left).or_default() += &count;
+                if let Some(right) = right {
+                    *next_counts.entry(right).or_default() += &count;
+                }
             }
+
+            counts = next_counts;
+        }
+
+        Ok(counts.values().fold(Count::default(), |mut acc, count| {
+            acc += count;
+            acc
+        }))
+    }
+
+    // Finds the first blink (if any, within `blinks` blinks) that each initial stone has a descendant with the
+    // value `value` (where blink 0 is the stone itself).
+    pub fn first_produced(
+        &mut self,
+        value: u64,
+        blinks: usize,
+    ) -> Result<Vec<FirstProduced>, Box<dyn error::Error>> {
+        let mut produced = vec![];
+
+        for stone in self.initial.clone() {
+            // We only care about which values show up, not how many of each there are.
+            let mut values = HashSet::from([stone]);
+            let mut first = None;
+
+            for blink in 0..=blinks {
+                if values.contains(&value) {
+                    first = Some(blink);
+                    break;
+                }
+
+                let mut next_values = HashSet::with_capacity(values.len());
+                for stone in values {
+                    let (left, right) = self.split(stone, blink + 1)?;
+
+                    next_values.insert(left);
+                    next_values.extend(right);
+                }
+
+                values = next_values;
+            }
+
+            produced.push(FirstProduced {
+                stone,
+                blink: first,
+            });
+        }
+
+        Ok(produced)
+    }
+
+    // Works out exactly how many stones there'll be after `blinks` more blinks, without blinking.
+    //
+    // This only works once every value the stones can turn into already has a known split (i.e. the stones have
+    // converged). From then on, each value's count is a fixed linear function of the last blink's counts, so we
+    // can just apply that function over and over on a plain list of counts (no hashing or rules involved).
+    pub fn extrapolate(&self, blinks: usize) -> Result<Count, Box<dyn error::Error>> {
+        let values = self.splits.keys().copied().collect::<Vec<_>>();
+        let indexes = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (*value, i))
+            .collect::<HashMap<_, _>>();
+
+        let index_of = |value: &u64| {
+            indexes
+                .get(value)
+                .copied()
+                .ok_or("expected stones to have converged before extrapolating")
         };
 
-        // Record how this stone should split.
-        self.splits.insert(stone, split);
+        // Where each value's stones end up after a blink.
+        let transitions = values
+            .iter()
+            .map(|value| {
+                let (left, right) = self.splits[value];
+
+                Ok((index_of(&left)?, right.as_ref().map(index_of).transpose()?))
+            })
+            .collect::<Result<Vec<_>, Box<dyn error::Error>>>()?;
 
-        split
+        let mut counts = vec![Count::default(); values.len()];
+        for (value, count) in &self.curr_blink_counts {
+            counts[index_of(value)?] += count;
+        }
+
+        for _ in 0..blinks {
+            let mut next_counts = vec![Count::default(); values.len()];
+
+            for (count, (left, right)) in counts.iter().zip(&transitions) {
+                if count.is_zero() {
+                    continue;
+                }
+
+                next_counts[*left] += count;
+                if let Some(right) = right {
+                    next_counts[*right] += count;
+                }
+            }
+
+            counts = next_counts;
+        }
+
+        Ok(counts.iter().fold(Count::default(), |mut acc, count| {
+            acc += count;
+            acc
+        }))
     }
 
-    pub fn count(&self) -> u64 {
+    pub fn count(&self) -> Count {
         self.curr_blink_counts
-            .iter()
-            .fold(0, |acc, kvp| acc + *kvp.1)
+            .values()
+            .fold(Count::default(), |mut acc, count| {
+                acc += count;
+                acc
+            })
     }
 
     pub fn distinct(&self) -> &HashSet<u64> {
@@ -118,25 +292,38 @@ impl Stones {
     }
 }
 
-fn num_digits(num: u64) -> usize {
-    let mut res = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stones() -> Stones {
+        let rules = "equals 0 => replace 1\ndigits even => split\nalways => multiply 2024"
+            .parse()
+            .unwrap();
 
-    let mut curr = num;
-    while curr > 0 {
-        curr /= 10;
-        res += 1;
+        Stones::from_reader("125 17".as_bytes(), rules)
     }
 
-    res
-}
+    #[test]
+    fn extrapolating_matches_blinking() {
+        let mut blinked = stones();
+        for _ in 0..120 {
+            blinked.blink().unwrap();
+        }
 
-fn split_num(num: u64, digits: usize) -> (u64, u64) {
-    // Get the left divisor.
-    // e.g. num=5214; digits=4; 10^(4/2) = 100; left = 5214 / 100 = 52; right = 5214 % 100 = 14.
-    let left_divisor = 10u64.pow((digits / 2) as u32);
+        let mut converged = stones();
+        for _ in 0..100 {
+            converged.blink().unwrap();
+        }
+
+        assert_eq!(converged.extrapolate(20).unwrap(), blinked.count());
+    }
 
-    let left = num / left_divisor;
-    let right = num % left_divisor;
+    #[test]
+    fn extrapolating_needs_convergence() {
+        let mut stones = stones();
+        stones.blink().unwrap();
 
-    (left, right)
+        assert!(stones.extrapolate(10).is_err());
+    }
 }
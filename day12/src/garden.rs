@@ -1,7 +1,5 @@
 use std::{collections::HashSet, error, io::BufRead};
 
-use maplit::hashset;
-
 pub struct Garden {
     grid: Vec<Vec<char>>,
 }
@@ -10,8 +8,7 @@ impl Garden {
     pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, Box<dyn error::Error>> {
         let mut grid = vec![];
 
-        let mut lines = reader.lines();
-        while let Some(line) = lines.next() {
+        for line in reader.lines() {
             let line = line?;
 
             let mut row = vec![];
@@ -26,79 +23,42 @@ impl Garden {
     }
 
     pub fn regions(&self) -> Vec<GardenRegion> {
-        let mut regions: Vec<GardenRegion> = vec![];
-
-        // Keep track of plots we've already associated with a region.
-        let mut marked = HashSet::new();
-
-        // Iterate over each plot until we find a cell we haven't associated with a region.
-        let mut iter = self.iter();
-        while let Some(curr) = iter.next() {
-            let curr = (curr.0 as i32, curr.1 as i32);
-            if marked.contains(&curr) {
-                continue;
-            }
-
-            let region_name = self.try_get(curr).unwrap();
-            let (mut min_x, mut min_y) = (None, None);
-            let (mut max_x, mut max_y) = (None, None);
-
-            // Now that we've found a plot that we haven't marked yet, run a search
-            // to find all neighboring plots of the same name.
-            //
-            // We can do that by continously expanding a frontier that will find plots of the
-            // same name.
-            let mut frontier = vec![curr];
-            let mut seen = hashset![curr];
-
-            let mut region_perimeter = 0;
-            let mut region_area = 0;
-            while !frontier.is_empty() {
-                let plot = frontier.pop().unwrap();
-                marked.insert(plot);
-                region_area += 1;
-
-                let (x, y) = plot;
-
-                // Do any sort of min/max memorizations so we can keep track of the bounds of this region.
-                min_x.replace_if(x, |val| x < *val);
-                max_x.replace_if(x, |val| x > *val);
-                min_y.replace_if(y, |val| y < *val);
-                max_y.replace_if(y, |val| y > *val);
-
-                // Check each of the neighbors to see if they're part of the contiguous region.
-                let neighbors = [(x, y + 1), (x + 1, y), (x, y - 1), (x - 1, y)];
-
-                for neighbor in neighbors {
-                    match self.try_get(neighbor) {
-                        None => region_perimeter += 1,
-                        Some(neighbor_name) => {
-                            if *neighbor_name == *region_name {
-                                if !seen.contains(&neighbor) {
-                                    seen.insert(neighbor);
-                                    frontier.push(neighbor);
-                                }
-                            } else {
-                                region_perimeter += 1;
-                            }
-                        }
-                    }
-                }
+        let plots = self.iter().map(|(x, y)| (x as i32, y as i32));
+
+        // A region is just a group of plots with the same name that all touch each other.
+        search::connected_components(plots, |plot| {
+            let name = self.try_get(*plot);
+
+            Self::neighbors(*plot)
+                .into_iter()
+                .filter(|neighbor| self.try_get(*neighbor) == name)
+                .collect::<Vec<_>>()
+        })
+        .into_iter()
+        .map(|plots| {
+            let name = *self.try_get(plots[0]).unwrap();
+
+            // Every side of a plot that doesn't touch another plot in the region is part of the fence.
+            let perimeter = plots
+                .iter()
+                .flat_map(|plot| Self::neighbors(*plot))
+                .filter(|neighbor| self.try_get(*neighbor) != Some(&name))
+                .count();
+
+            GardenRegion {
+                name,
+                area: plots.len(),
+                perimeter,
+                plots: plots.into_iter().collect(),
             }
+        })
+        .collect()
+    }
 
-            regions.push(GardenRegion {
-                name: *region_name,
-                area: region_area,
-                perimeter: region_perimeter,
-                bounds: Bounds {
-                    top_left: (min_x.unwrap() as usize, min_y.unwrap() as usize),
-                    bottom_right: (max_x.unwrap() as usize, max_y.unwrap() as usize),
-                },
-                plots: seen.into_iter().collect(),
-            });
-        }
+    fn neighbors(plot: (i32, i32)) -> [(i32, i32); 4] {
+        let (x, y) = plot;
 
-        regions
+        [(x, y + 1), (x + 1, y), (x, y - 1), (x - 1, y)]
     }
 
     pub fn try_get(&self, point: (i32, i32)) -> Option<&char> {
@@ -129,25 +89,15 @@ pub struct GardenRegion {
     pub name: char,
     pub area: usize,
     pub perimeter: usize,
-    pub bounds: Bounds,
     pub plots: HashSet<(i32, i32)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Bounds {
     pub top_left: (usize, usize),
     pub bottom_right: (usize, usize),
 }
 
-impl Bounds {
-    pub fn iter(&self) -> BoundsIterator {
-        BoundsIterator {
-            curr: None,
-            bounds: self.clone(),
-        }
-    }
-}
-
 pub struct BoundsIterator {
     curr: Option<(usize, usize)>,
     bounds: Bounds,
@@ -158,22 +108,22 @@ impl Iterator for BoundsIterator {
 
     fn next(&mut self) -> Option<Self::Item> {
         // If we haven't started yet, start at the top-left.
-        if let None = self.curr {
-            self.curr = Some(self.bounds.top_left.clone());
+        if self.curr.is_none() {
+            self.curr = Some(self.bounds.top_left);
             return self.curr;
         }
 
         let curr = self.curr.as_mut().unwrap();
 
         // If we can travel right, do it!
-        if curr.0 + 1 <= self.bounds.bottom_right.0 {
+        if curr.0 < self.bounds.bottom_right.0 {
             curr.0 += 1;
 
             return self.curr;
         }
 
         // Otherwise, if we can travel down, do it and move x back to min_x.
-        if curr.1 + 1 <= self.bounds.bottom_right.1 {
+        if curr.1 < self.bounds.bottom_right.1 {
             curr.1 += 1;
             curr.0 = self.bounds.top_left.0;
 
@@ -184,26 +134,3 @@ impl Iterator for BoundsIterator {
         None
     }
 }
-
-trait ReplaceIf {
-    type Item;
-
-    fn replace_if<F: FnOnce(&Self::Item) -> bool>(&mut self, item: Self::Item, condition: F);
-}
-
-impl<T> ReplaceIf for Option<T> {
-    type Item = T;
-
-    fn replace_if<F: FnOnce(&Self::Item) -> bool>(&mut self, item: Self::Item, condition: F) {
-        if self.is_none() {
-            self.replace(item);
-            return;
-        }
-
-        if let Some(val) = self {
-            if condition(val) {
-                self.replace(item);
-            }
-        }
-    }
-}
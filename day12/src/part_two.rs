@@ -1,7 +1,7 @@
 use std::io;
 use std::{error, fs};
 
-use crate::{Cli, Garden, GardenRegion};
+use crate::{Cli, GardenRegion};
 
 pub fn part_two(
     args: &Cli,
@@ -13,32 +13,9 @@ pub fn part_two(
 
     let regions = garden.regions();
     for region in regions {
-        // Find the number of sides for each region by performing scans horizontally and vertically.
-        // Below is the algorithm for the horizontal scan, but the vertical scan would just be the inverse in terms of directions and orientation.
-        //
-        // for each plot:
-        //   if the plot doesn't belong to the region:
-        //     reset top state
-        //     reset bottom state
-        //   else:
-        //     if the plot above is part of the region:
-        //       if tracking top:
-        //         tracking_top = false
-        //     else:
-        //       if top is not being tracked:
-        //         num_sides++
-        //         tracking_top = true
-        //
-        //     if the plot below is part of the region:
-        //       if tracking bottom:
-        //         tracking_bottom = false
-        //     else:
-        //       if bottom is not being tracked:
-        //         num_sides++
-        //         tracking_bottom = true
-
-        let num_sides = scan_for_sides(args, &garden, &region, ScanDirection::Horizontal)
-            + scan_for_sides(args, &garden, &region, ScanDirection::Vertical);
+        // A region has as many sides as it has corners (every side starts at one corner and ends at the next),
+        // and corners are easy to spot one plot at a time.
+        let num_sides = count_corners(&region);
 
         if args.debug {
             println!(
@@ -47,7 +24,7 @@ pub fn part_two(
             );
         }
 
-        total += num_sides * region.area as u32;
+        total += num_sides * region.area;
     }
 
     println!("{}", total);
@@ -55,100 +32,97 @@ pub fn part_two(
     Ok(())
 }
 
-fn scan_for_sides(
-    args: &Cli,
-    garden: &Garden,
-    region: &GardenRegion,
-    direction: ScanDirection,
-) -> u32 {
-    let mut num_sides = 0;
-
-    let x_range = (region.bounds.top_left.0, region.bounds.bottom_right.0);
-    let y_range = (region.bounds.top_left.1, region.bounds.bottom_right.1);
-
-    let (outer_range, inner_range) = match direction {
-        ScanDirection::Horizontal => (y_range, x_range),
-        ScanDirection::Vertical => (x_range, y_range),
-    };
-
-    for i in outer_range.0..=outer_range.1 {
-        let mut tracking = [false, false];
-
-        for j in inner_range.0..=inner_range.1 {
-            let plot = match direction {
-                ScanDirection::Horizontal => (j as i32, i as i32),
-                ScanDirection::Vertical => (i as i32, j as i32),
-            };
-
-            let plot_region = garden.try_get(plot).unwrap();
-
-            // If this plot has the same region name but isn't _actually_ part of this region,
-            // just ignore it! It's probably in an isolated island somewhere.
-            if *plot_region == region.name && !region.plots.contains(&plot) {
-                continue;
+fn count_corners(region: &GardenRegion) -> usize {
+    let mut corners = 0;
+
+    for (x, y) in &region.plots {
+        // Check each corner of the plot by looking at the two plots next to it on either side of the corner, and
+        // the one diagonal from it.
+        //
+        // Only plots that are actually in this region count; a plot with the same name could be part of some
+        // other region entirely.
+        for (dx, dy) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+            let horizontal = region.plots.contains(&(x + dx, *y));
+            let vertical = region.plots.contains(&(*x, y + dy));
+            let diagonal = region.plots.contains(&(x + dx, y + dy));
+
+            // If neither neighbor is in the region, it's an outside corner (like the corners of a square)...
+            if !horizontal && !vertical {
+                corners += 1;
             }
 
-            if args.debug {
-                println!("plot: {:?}", &plot);
+            // ...and if both are but the diagonal isn't, it's an inside corner (like the inside of an L).
+            if horizontal && vertical && !diagonal {
+                corners += 1;
             }
+        }
+    }
 
-            if *plot_region != region.name {
-                if args.debug && tracking[0] {
-                    println!("stopped tracking 0 at {:?}", &plot);
-                }
+    corners
+}
 
-                if args.debug && tracking[1] {
-                    println!("stopped tracking 1 at {:?}", &plot);
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Garden;
 
-                tracking = [false, false];
-                continue;
-            }
+    fn total_price(garden: &str) -> usize {
+        let garden = Garden::from_reader(garden.as_bytes()).unwrap();
 
-            let neighbors = match direction {
-                ScanDirection::Horizontal => [(plot.0, plot.1 - 1), (plot.0, plot.1 + 1)],
-                ScanDirection::Vertical => [(plot.0 - 1, plot.1), (plot.0 + 1, plot.1)],
-            };
-
-            for i in 0..2 {
-                let neighbor = &neighbors[i];
-
-                if args.debug {
-                    println!("checking {:?}", neighbor);
-                }
-
-                match garden.try_get(neighbors[i]) {
-                    Some(name) if *name == region.name => {
-                        if args.debug && tracking[i] {
-                            println!("stopped tracking {} at {:?}", i, &plot);
-                        }
-
-                        tracking[i] = false
-                    }
-                    _ => {
-                        if !tracking[i] {
-                            if args.debug {
-                                println!("started tracking {} at {:?}", i, &plot);
-                            }
-
-                            num_sides += 1;
-                            tracking[i] = true;
-                        }
-                    }
-                }
-            }
-        }
+        garden
+            .regions()
+            .iter()
+            .map(|region| region.area * count_corners(region))
+            .sum()
     }
 
-    if args.debug {
-        println!("num_sides ({:?}): {:?}", &direction, &num_sides);
+    #[test]
+    fn small_example() {
+        assert_eq!(total_price(include_str!("../input.test")), 80);
     }
 
-    num_sides
-}
+    #[test]
+    fn nested_example() {
+        assert_eq!(total_price(include_str!("../input.test.2")), 436);
+    }
+
+    #[test]
+    fn large_example() {
+        assert_eq!(total_price(include_str!("../input.test.3")), 1206);
+    }
 
-#[derive(Debug)]
-pub enum ScanDirection {
-    Horizontal,
-    Vertical,
+    #[test]
+    fn e_shaped_example() {
+        assert_eq!(total_price(include_str!("../input.test.4")), 236);
+    }
+
+    #[test]
+    fn checkerboard_example() {
+        assert_eq!(total_price(include_str!("../input.test.5")), 368);
+    }
+
+    #[test]
+    fn same_name_regions_are_counted_separately() {
+        // The A in the middle isn't part of the A around the outside, even though it's surrounded by it.
+        let garden = Garden::from_reader(
+            "\
+AAAAA
+ABBBA
+ABABA
+ABBBA
+AAAAA
+"
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let mut sides = garden
+            .regions()
+            .iter()
+            .map(|region| (region.name, region.area, count_corners(region)))
+            .collect::<Vec<_>>();
+        sides.sort();
+
+        assert_eq!(sides, [('A', 1, 4), ('A', 16, 8), ('B', 8, 8)]);
+    }
 }
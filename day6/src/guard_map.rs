@@ -1,8 +1,9 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet, VecDeque},
     error,
     fmt::{Debug, Write},
     io,
+    str::FromStr,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -13,28 +14,239 @@ pub enum GuardHeading {
     Left,
 }
 
+impl GuardHeading {
+    fn turn(&self, turn: Turn) -> GuardHeading {
+        match (turn, self) {
+            (Turn::Right, GuardHeading::Up) => GuardHeading::Right,
+            (Turn::Right, GuardHeading::Right) => GuardHeading::Down,
+            (Turn::Right, GuardHeading::Down) => GuardHeading::Left,
+            (Turn::Right, GuardHeading::Left) => GuardHeading::Up,
+            (Turn::Left, GuardHeading::Up) => GuardHeading::Left,
+            (Turn::Left, GuardHeading::Right) => GuardHeading::Up,
+            (Turn::Left, GuardHeading::Down) => GuardHeading::Right,
+            (Turn::Left, GuardHeading::Left) => GuardHeading::Down,
+            (Turn::Reverse, GuardHeading::Up) => GuardHeading::Down,
+            (Turn::Reverse, GuardHeading::Right) => GuardHeading::Left,
+            (Turn::Reverse, GuardHeading::Down) => GuardHeading::Up,
+            (Turn::Reverse, GuardHeading::Left) => GuardHeading::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Turn {
+    Left,
+    Right,
+    Reverse,
+}
+
+impl FromStr for Turn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "left" | "l" => Ok(Turn::Left),
+            "right" | "r" => Ok(Turn::Right),
+            "reverse" | "b" => Ok(Turn::Reverse),
+            turn => Err(format!("unexpected turn {}", turn)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GuardRule {
+    TurnLeft,
+    #[default]
+    TurnRight,
+    Reverse,
+    Sequence(Vec<Turn>),
+}
+
+impl GuardRule {
+    // Returns the turn a guard should make on their nth collision with an obstacle.
+    fn turn(&self, nth: usize) -> Turn {
+        match self {
+            GuardRule::TurnLeft => Turn::Left,
+            GuardRule::TurnRight => Turn::Right,
+            GuardRule::Reverse => Turn::Reverse,
+            GuardRule::Sequence(turns) => turns[nth % turns.len()],
+        }
+    }
+
+    // Returns how many collisions it takes before the rule repeats itself.
+    fn period(&self) -> usize {
+        match self {
+            GuardRule::Sequence(turns) => turns.len(),
+            _ => 1,
+        }
+    }
+}
+
+impl FromStr for GuardRule {
+    type Err = String;
+
+    // Parses a single turn (e.g. "right") or a comma-delimited sequence of turns (e.g. "left,left,right").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let turns = s
+            .split(",")
+            .map(|turn| turn.parse())
+            .collect::<Result<Vec<Turn>, _>>()?;
+
+        Ok(match turns[..] {
+            [Turn::Left] => GuardRule::TurnLeft,
+            [Turn::Right] => GuardRule::TurnRight,
+            [Turn::Reverse] => GuardRule::Reverse,
+            _ => GuardRule::Sequence(turns),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum MapItem {
-    Guard(GuardHeading),
     Obstacle,
     ProbeObstacle,
     Free,
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PositionAndHeading {
     pub position: (usize, usize),
     pub heading: GuardHeading,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GuardEvent {
+    Moved {
+        guard: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    Turned {
+        guard: usize,
+        at: (usize, usize),
+        from_heading: GuardHeading,
+        to_heading: GuardHeading,
+        obstacle: (usize, usize),
+    },
+    LeftMap {
+        guard: usize,
+        from: (usize, usize),
+    },
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Exited {
+        visited: HashSet<(usize, usize)>,
+        steps: usize,
+    },
+    Looped {
+        cycle_start: usize,
+        cycle_length: usize,
+        states: Vec<PositionAndHeading>,
+    },
+}
+
+#[derive(Debug)]
+pub struct LoopObstruction {
+    pub position: (usize, usize),
+    pub guard: usize,
+    pub cycle_length: usize,
+    pub states: Vec<PositionAndHeading>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Guard {
+    start: PositionAndHeading,
+    position: Option<(usize, usize)>,
+    heading: GuardHeading,
+    collisions: usize,
+
+    visited: HashSet<(usize, usize)>,
+}
+
+impl Guard {
+    fn new(position: (usize, usize), heading: GuardHeading) -> Self {
+        Guard {
+            start: PositionAndHeading {
+                position,
+                heading: heading.clone(),
+            },
+            position: Some(position),
+            heading,
+            collisions: 0,
+            visited: HashSet::from([position]),
+        }
+    }
+
+    pub fn start(&self) -> &PositionAndHeading {
+        &self.start
+    }
+
+    pub fn position(&self) -> Option<PositionAndHeading> {
+        self.position.map(|position| PositionAndHeading {
+            position,
+            heading: self.heading.clone(),
+        })
+    }
+
+    pub fn visited(&self) -> &HashSet<(usize, usize)> {
+        &self.visited
+    }
+}
+
+// Watches a single guard's states and reports when the guard ends up somewhere it's already been.
+pub struct LoopDetector {
+    history: Vec<PositionAndHeading>,
+
+    // Keyed by the guard's position, heading and where they are in their rule's turn sequence
+    // (since the same position and heading can lead somewhere else if the next turn is different).
+    seen: HashMap<(PositionAndHeading, usize), usize>,
+}
+
+impl LoopDetector {
+    pub fn new(guard: &Guard, rule: &GuardRule) -> Self {
+        let mut detector = LoopDetector {
+            history: vec![],
+            seen: HashMap::new(),
+        };
+
+        detector.observe(guard, rule);
+
+        detector
+    }
+
+    // Records the guard's current state; if the guard has been in this state before, returns the loop.
+    pub fn observe(&mut self, guard: &Guard, rule: &GuardRule) -> Option<Outcome> {
+        let state = guard.position()?;
+        let key = (state.clone(), guard.collisions % rule.period());
+
+        if let Some(&cycle_start) = self.seen.get(&key) {
+            return Some(Outcome::Looped {
+                cycle_start,
+                cycle_length: self.history.len() - cycle_start,
+                states: self.history[cycle_start..].to_vec(),
+            });
+        }
+
+        self.seen.insert(key, self.history.len());
+        self.history.push(state);
+
+        None
+    }
+
+    pub fn steps(&self) -> usize {
+        self.history.len()
+    }
+}
+
 #[derive(Clone)]
 pub struct Map {
     map: Vec<Vec<MapItem>>,
     dims: (usize, usize),
-    guard_pos: Option<(usize, usize)>,
+    rule: GuardRule,
 
-    guard_visited_pos: HashSet<(usize, usize)>,
-    guard_hit_obstacle_pos: Option<(usize, usize)>,
+    guards: Vec<Guard>,
 }
 
 impl Map {
@@ -42,32 +254,21 @@ impl Map {
         self.dims
     }
 
-    pub fn guard_visited_positions<'a>(
-        &'a self,
-    ) -> Box<dyn Iterator<Item = &'a (usize, usize)> + 'a> {
-        Box::new(self.guard_visited_pos.iter())
+    pub fn guards(&self) -> &[Guard] {
+        &self.guards
     }
 
-    pub fn guard_position(&self) -> Option<PositionAndHeading> {
-        match self.guard_pos {
-            None => None,
-            Some(guard_pos) => Some(PositionAndHeading {
-                position: guard_pos,
-                heading: match &self.map[guard_pos.1][guard_pos.0] {
-                    MapItem::Guard(heading) => heading.clone(),
-                    _ => panic!("expected guard at guard position"),
-                },
-            }),
-        }
+    pub fn rule(&self) -> &GuardRule {
+        &self.rule
     }
 
-    pub fn guard_hit_obstacle_position(&self) -> &Option<(usize, usize)> {
-        &self.guard_hit_obstacle_pos
+    pub fn set_rule(&mut self, rule: GuardRule) {
+        self.rule = rule;
     }
 
     pub fn from_reader<R: io::BufRead>(reader: R) -> Result<Self, Box<dyn error::Error>> {
         let mut map = vec![];
-        let mut guard_position = None;
+        let mut guards = vec![];
 
         for (y, line) in reader.lines().enumerate() {
             let line = line?;
@@ -77,18 +278,19 @@ impl Map {
                 let item = match ch {
                     '#' => MapItem::Obstacle,
                     '.' => MapItem::Free,
-                    h @ _ => {
-                        let guard = MapItem::Guard(match h {
+                    h => {
+                        let heading = match h {
                             '^' => GuardHeading::Up,
                             '>' => GuardHeading::Right,
                             'v' => GuardHeading::Down,
                             '<' => GuardHeading::Left,
                             _ => return Err(format!("unexpected guard heading {}", h).into()),
-                        });
+                        };
 
-                        guard_position = Some((x, y));
+                        // Guards are tracked separately from the map, so the spot they start on is free.
+                        guards.push(Guard::new((x, y), heading));
 
-                        guard
+                        MapItem::Free
                     }
                 };
 
@@ -100,43 +302,58 @@ impl Map {
 
         let dims = match map.len() {
             0 => (0, 0),
-            len @ _ => (map[0].len(), len),
+            len => (map[0].len(), len),
         };
 
         Ok(Map {
             map,
             dims,
-            guard_pos: guard_position.clone(),
-            guard_visited_pos: match guard_position {
-                Some(position) => HashSet::from([position]),
-                None => HashSet::new(),
-            },
-            guard_hit_obstacle_pos: None,
+            rule: GuardRule::default(),
+            guards,
         })
     }
 
-    pub fn items(&self) -> MapIterator {
+    pub fn get_item(&self, position: (usize, usize)) -> Option<&MapItem> {
+        self.map.get(position.1).and_then(|row| row.get(position.0))
+    }
+
+    pub fn items(&self) -> MapIterator<'_> {
         MapIterator {
-            guard_map: &self,
+            guard_map: self,
             dims: self.dims,
             pos: None,
             done: false,
         }
     }
 
-    pub fn tick(&mut self) -> Result<bool, Box<dyn error::Error>> {
-        if self.guard_pos.is_none() {
-            return Ok(false);
+    // Moves every guard that's still on the map one step.
+    pub fn tick(&mut self) -> Result<Vec<GuardEvent>, Box<dyn error::Error>> {
+        let mut events = vec![];
+
+        for guard in 0..self.guards.len() {
+            if let Some(event) = self.tick_guard(guard)? {
+                events.push(event);
+            }
         }
 
-        // Reset state from last tick.
-        self.guard_hit_obstacle_pos = None;
+        Ok(events)
+    }
 
-        // Figure out the new guard position:
-        let guard_pos = self.guard_pos.unwrap();
-        let guard_heading = match &self.map[guard_pos.1][guard_pos.0] {
-            MapItem::Guard(heading) => heading.clone(),
-            _ => return Err(format!("expected guard at {:?}", guard_pos).into()),
+    pub fn tick_guard(
+        &mut self,
+        guard: usize,
+    ) -> Result<Option<GuardEvent>, Box<dyn error::Error>> {
+        let (guard_pos, guard_heading) = {
+            let guard_state = self
+                .guards
+                .get(guard)
+                .ok_or(format!("unknown guard {}", guard))?;
+
+            // If the guard isn't on the map, there's nothing left to do.
+            match guard_state.position() {
+                None => return Ok(None),
+                Some(PositionAndHeading { position, heading }) => (position, heading),
+            }
         };
 
         // Figure out our guard's new position if they can continue going in the current heading.
@@ -149,61 +366,153 @@ impl Map {
 
         // Check if the guard is going to go off map if they keep going.
         let guard_off_map = match proposed_guard_pos {
-            (x, _) if x < 0 || x as usize >= self.map[0].len() => true,
-            (_, y) if y < 0 || y as usize >= self.map.len() => true,
+            (x, _) if x < 0 || x as usize >= self.dims.0 => true,
+            (_, y) if y < 0 || y as usize >= self.dims.1 => true,
             _ => false,
         };
 
-        // If the guard is going off map, change the guard_position to None and let the caller know
-        // we're done!
+        // If the guard is going off map, change their position to None and let the caller know
+        // they're done!
         if guard_off_map {
-            self.move_guard(None, &guard_heading);
-            return Ok(false);
+            self.guards[guard].position = None;
+            return Ok(Some(GuardEvent::LeftMap {
+                guard,
+                from: guard_pos,
+            }));
         }
 
-        // ...otherwise, check if we're going to collide with an object; if so, rotate 90 degrees; otherwise,
-        // go to that position!
+        // ...otherwise, check if we're going to collide with an object; if so, turn according to
+        // the rules; otherwise, go to that position!
+        //
+        // Guards don't block each other, so it's fine for more than one guard to be in the same spot.
         let proposed_guard_pos = (proposed_guard_pos.0 as usize, proposed_guard_pos.1 as usize);
-        match &self.map[proposed_guard_pos.1][proposed_guard_pos.0] {
+        let event = match &self.map[proposed_guard_pos.1][proposed_guard_pos.0] {
             MapItem::Obstacle | MapItem::ProbeObstacle => {
-                let new_guard_heading = match guard_heading {
-                    GuardHeading::Up => GuardHeading::Right,
-                    GuardHeading::Right => GuardHeading::Down,
-                    GuardHeading::Down => GuardHeading::Left,
-                    GuardHeading::Left => GuardHeading::Up,
-                };
+                let guard_state = &mut self.guards[guard];
+
+                let new_guard_heading = guard_heading.turn(self.rule.turn(guard_state.collisions));
 
-                // Mark that we hit this obstacle.
-                self.guard_hit_obstacle_pos = Some(proposed_guard_pos);
+                guard_state.heading = new_guard_heading.clone();
+                guard_state.collisions += 1;
 
-                // Move the guard.
-                self.move_guard(Some(guard_pos), &new_guard_heading);
+                GuardEvent::Turned {
+                    guard,
+                    at: guard_pos,
+                    from_heading: guard_heading,
+                    to_heading: new_guard_heading,
+                    obstacle: proposed_guard_pos,
+                }
             }
-            MapItem::Free => self.move_guard(Some(proposed_guard_pos), &guard_heading),
-            MapItem::Guard(_) => {
-                return Err(format!("somehow tried to move the guard into another guard").into())
+            MapItem::Free => {
+                let guard_state = &mut self.guards[guard];
+
+                guard_state.position = Some(proposed_guard_pos);
+                guard_state.visited.insert(proposed_guard_pos);
+
+                GuardEvent::Moved {
+                    guard,
+                    from: guard_pos,
+                    to: proposed_guard_pos,
+                }
             }
+        };
+
+        Ok(Some(event))
+    }
+
+    pub fn patrol(&mut self) -> Patrol<'_> {
+        Patrol {
+            map: self,
+            pending: VecDeque::new(),
+            done: false,
         }
+    }
+
+    // Runs each guard's patrol until they either leave the map or get stuck in a loop.
+    pub fn simulate(&mut self) -> Result<Vec<Outcome>, Box<dyn error::Error>> {
+        let mut outcomes = vec![];
 
-        Ok(true)
+        // Guards don't interact with each other, so we can just run them one at a time.
+        for guard in 0..self.guards.len() {
+            let mut detector = LoopDetector::new(&self.guards[guard], &self.rule);
+
+            let outcome = loop {
+                match self.tick_guard(guard)? {
+                    None | Some(GuardEvent::LeftMap { .. }) => {
+                        break Outcome::Exited {
+                            visited: self.guards[guard].visited.clone(),
+                            steps: detector.steps(),
+                        }
+                    }
+                    Some(_) => {
+                        if let Some(outcome) = detector.observe(&self.guards[guard], &self.rule) {
+                            break outcome;
+                        }
+                    }
+                }
+            };
+
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
     }
 
-    fn move_guard(&mut self, position: Option<(usize, usize)>, heading: &GuardHeading) {
-        // Free up the old position if the guard was on the map.
-        if let Some(old_position) = self.guard_pos {
-            self.map[old_position.1][old_position.0] = MapItem::Free;
+    // Finds every position where placing a single obstacle would get a guard stuck in a loop.
+    pub fn loop_obstructions(&self) -> Result<Vec<LoopObstruction>, Box<dyn error::Error>> {
+        // An obstacle can only change a guard's patrol if it's somewhere the guard would have gone,
+        // so run the patrol once without any new obstacles to figure out where we need to check.
+        let mut candidates = HashSet::new();
+        {
+            let mut map = self.clone();
+            map.simulate()?;
+
+            for guard in map.guards() {
+                candidates.extend(guard.visited().iter().copied());
+            }
+        }
+
+        // We can't put an obstacle where a guard is standing.
+        for guard in self.guards() {
+            candidates.remove(&guard.start().position);
         }
 
-        // Update the map and record the position as visited if the guard is still on the map.
-        if let Some(position) = position {
-            self.map[position.1][position.0] = MapItem::Guard(heading.clone());
+        let mut obstructions = vec![];
 
-            // Record the position in the list of visited positions.
-            self.guard_visited_pos.insert(position);
+        let mut items = self.items();
+        while let Some(item) = items.next() {
+            let position = items.curr().unwrap();
+            if !matches!(item, MapItem::Free) || !candidates.contains(&position) {
+                continue;
+            }
+
+            // Place an obstacle at the position on a copy of the map and see if anyone gets stuck.
+            let mut map = self.clone();
+            map.set_item(position, MapItem::ProbeObstacle)?;
+
+            let looped =
+                map.simulate()?.into_iter().enumerate().find_map(
+                    |(guard, outcome)| match outcome {
+                        Outcome::Looped {
+                            cycle_length,
+                            states,
+                            ..
+                        } => Some(LoopObstruction {
+                            position,
+                            guard,
+                            cycle_length,
+                            states,
+                        }),
+                        Outcome::Exited { .. } => None,
+                    },
+                );
+
+            if let Some(obstruction) = looped {
+                obstructions.push(obstruction);
+            }
         }
 
-        // Update the guard position.
-        self.guard_pos = position;
+        Ok(obstructions)
     }
 
     pub fn set_item(
@@ -234,19 +543,29 @@ impl Debug for Map {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (y, row) in self.map.iter().enumerate() {
             for (x, item) in row.iter().enumerate() {
-                let ch = match item {
-                    MapItem::Guard(heading) => match heading {
+                let guard = self
+                    .guards
+                    .iter()
+                    .filter_map(|guard| guard.position())
+                    .find(|guard| guard.position == (x, y));
+
+                let ch = match (item, guard) {
+                    (_, Some(guard)) => match guard.heading {
                         GuardHeading::Up => '^',
                         GuardHeading::Right => '>',
                         GuardHeading::Down => 'v',
                         GuardHeading::Left => '<',
                     },
-                    MapItem::Obstacle => '#',
-                    MapItem::ProbeObstacle => 'O',
-                    MapItem::Free =>
-                    // If this spot has been visited, mark it; otherwise, show it as free.
+                    (MapItem::Obstacle, _) => '#',
+                    (MapItem::ProbeObstacle, _) => 'O',
+                    (MapItem::Free, _) =>
+                    // If this spot has been visited by any guard, mark it; otherwise, show it as free.
                     {
-                        if self.guard_visited_pos.contains(&(x, y)) {
+                        if self
+                            .guards
+                            .iter()
+                            .any(|guard| guard.visited.contains(&(x, y)))
+                        {
                             'X'
                         } else {
                             '.'
@@ -264,6 +583,43 @@ impl Debug for Map {
     }
 }
 
+pub struct Patrol<'a> {
+    map: &'a mut Map,
+    pending: VecDeque<GuardEvent>,
+    done: bool,
+}
+
+impl Iterator for Patrol<'_> {
+    type Item = Result<GuardEvent, Box<dyn error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Hand out any events left over from the last tick before ticking again.
+        if let Some(event) = self.pending.pop_front() {
+            return Some(Ok(event));
+        }
+
+        if self.done {
+            return None;
+        }
+
+        match self.map.tick() {
+            Ok(events) if events.is_empty() => {
+                self.done = true;
+                None
+            }
+            Ok(events) => {
+                self.pending.extend(events);
+                self.pending.pop_front().map(Ok)
+            }
+            Err(err) => {
+                // Don't keep ticking a map that's in a bad state.
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
 pub struct MapIterator<'a> {
     guard_map: &'a Map,
     dims: (usize, usize),
@@ -274,10 +630,10 @@ pub struct MapIterator<'a> {
 
 impl<'a> MapIterator<'a> {
     pub fn curr(&self) -> Option<(usize, usize)> {
-        if self.done || self.pos.is_none() {
+        if self.done {
             None
         } else {
-            Some(self.pos.unwrap())
+            self.pos
         }
     }
 }
@@ -315,3 +671,90 @@ impl<'a> Iterator for MapIterator<'a> {
         Some(&self.guard_map.map[new_pos.1][new_pos.0])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Map {
+        Map::from_reader(include_str!("../input.test").as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn example_guard_exits() {
+        let outcomes = example().simulate().unwrap();
+
+        assert_eq!(outcomes.len(), 1);
+        match &outcomes[0] {
+            Outcome::Exited { visited, .. } => assert_eq!(visited.len(), 41),
+            outcome => panic!("expected the guard to exit, got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn example_loop_obstructions() {
+        let positions = example()
+            .loop_obstructions()
+            .unwrap()
+            .iter()
+            .map(|obstruction| obstruction.position)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(
+            positions,
+            HashSet::from([(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)])
+        );
+    }
+
+    #[test]
+    fn sequence_loops_only_when_turn_phase_repeats() {
+        let rule: GuardRule = "left,right".parse().unwrap();
+        let mut guard = Guard::new((0, 0), GuardHeading::Up);
+        let mut detector = LoopDetector::new(&guard, &rule);
+
+        // Same position and heading, but the next turn would be right instead of left.
+        guard.collisions = 1;
+        assert!(detector.observe(&guard, &rule).is_none());
+
+        // Back to the start of the sequence, so this really is a loop.
+        guard.collisions = 2;
+        assert!(matches!(
+            detector.observe(&guard, &rule),
+            Some(Outcome::Looped {
+                cycle_start: 0,
+                cycle_length: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn guards_are_simulated_separately() {
+        let mut map = Map::from_reader(
+            "\
+.#....
+.^..#.
+#.....
+...#..
+.....v
+"
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let outcomes = map.simulate().unwrap();
+
+        assert_eq!(outcomes.len(), 2);
+        assert!(matches!(outcomes[0], Outcome::Looped { .. }));
+        match &outcomes[1] {
+            Outcome::Exited { visited, .. } => assert_eq!(visited, &HashSet::from([(5, 4)])),
+            outcome => panic!("expected the second guard to exit, got {:?}", outcome),
+        }
+
+        assert_eq!(
+            map.guards()[0].visited(),
+            &HashSet::from([(1, 1), (2, 1), (3, 1), (3, 2), (2, 2), (1, 2)])
+        );
+        assert_eq!(map.guards()[1].visited(), &HashSet::from([(5, 4)]));
+    }
+}
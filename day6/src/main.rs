@@ -13,6 +13,8 @@ use part_two::part_two;
 
 mod guard_map;
 
+mod replay;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum DayPart {
     One,
@@ -22,14 +24,32 @@ enum DayPart {
 #[derive(Parser)]
 #[command()]
 struct Cli {
-    #[arg(long = "part", value_enum)]
-    part: DayPart,
+    #[arg(long = "part", value_enum, required_unless_present = "replay")]
+    part: Option<DayPart>,
 
     #[arg(long = "input")]
     input: Option<String>,
 
     #[arg(long = "debug", default_value_t = false)]
     debug: bool,
+
+    #[arg(long = "replay", default_value_t = false)]
+    replay: bool,
+
+    #[arg(long = "delay-ms", default_value_t = 100)]
+    delay_ms: u64,
+
+    #[arg(long = "probe")]
+    probe: Option<String>,
+
+    #[arg(long = "rule", default_value = "right")]
+    rule: guard_map::GuardRule,
+
+    #[arg(long = "list", default_value_t = false)]
+    list: bool,
+
+    #[arg(long = "render", default_value_t = false)]
+    render: bool,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
@@ -38,8 +58,13 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let input_path = cli.input.clone().unwrap_or("input".into());
     let file_reader = io::BufReader::new(fs::File::open(input_path)?);
 
+    if cli.replay {
+        return replay::replay(&cli, file_reader);
+    }
+
     match cli.part {
-        DayPart::One => part_one(&cli, file_reader),
-        DayPart::Two => part_two(&cli, file_reader),
+        Some(DayPart::One) => part_one(&cli, file_reader),
+        Some(DayPart::Two) => part_two(&cli, file_reader),
+        None => Err("expected --part".into()),
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     error, fs,
     io::{self},
 };
@@ -10,16 +11,56 @@ pub fn part_one(
     file_reader: io::BufReader<fs::File>,
 ) -> Result<(), Box<dyn error::Error>> {
     let mut map = guard_map::Map::from_reader(file_reader)?;
+    map.set_rule(args.rule.clone());
 
-    // Let the map tick until it's done.
-    while map.tick()? {
-        if args.debug {
-            println!("{:?}", map);
+    // If we're debugging, replay the patrol on a copy of the map so we can see each event.
+    //
+    // Guards that get stuck in a loop would patrol forever, so cap the replay at the size of the map.
+    if args.debug {
+        let mut debug_map = map.clone();
+        let max_events = {
+            let dims = map.dimensions();
+            dims.0 * dims.1 * 4 * map.guards().len()
+        };
+
+        for event in debug_map.patrol().take(max_events) {
+            println!("{:?}", event?);
+        }
+    }
+
+    // Let the map run until every guard leaves (or gets stuck).
+    let outcomes = map.simulate()?;
+
+    if args.debug {
+        println!("{:?}", map);
+    }
+
+    // Check the number of unique positions the guards went to.
+    let mut covered = HashSet::new();
+    for (guard, outcome) in outcomes.iter().enumerate() {
+        match outcome {
+            guard_map::Outcome::Exited { visited, steps } => {
+                if args.debug {
+                    println!("guard {} left the map after {} steps", guard, steps);
+                }
+
+                covered.extend(visited.iter().copied());
+            }
+            guard_map::Outcome::Looped { cycle_start, .. } => {
+                if args.debug {
+                    println!(
+                        "guard {} got stuck in a loop at step {}",
+                        guard, cycle_start
+                    );
+                }
+
+                // A looping guard still covers everywhere they went before (and during) the loop.
+                covered.extend(map.guards()[guard].visited().iter().copied());
+            }
         }
     }
 
-    // Check the number of unique positions the guard went to.
-    println!("{}", map.guard_visited_positions().collect::<Vec<_>>().len());
+    println!("{}", covered.len());
 
     Ok(())
 }
@@ -0,0 +1,287 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error, fs,
+    io::{self, Write},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    guard_map::{self, GuardEvent, GuardHeading, MapItem},
+    Cli,
+};
+
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_LINE: &str = "\x1b[K";
+const RESET: &str = "\x1b[0m";
+const TRAIL_COLOR: &str = "\x1b[36m";
+const GUARD_COLOR: &str = "\x1b[1;32m";
+const PROBE_COLOR: &str = "\x1b[1;33m";
+const LOOP_COLOR: &str = "\x1b[1;31m";
+
+const MIN_DELAY_MS: u64 = 1;
+const MAX_DELAY_MS: u64 = 5000;
+
+#[derive(Default, Clone, Copy)]
+struct Trail {
+    vertical: bool,
+    horizontal: bool,
+}
+
+enum Command {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    Quit,
+}
+
+pub fn replay(
+    args: &Cli,
+    file_reader: io::BufReader<fs::File>,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut map = guard_map::Map::from_reader(file_reader)?;
+    map.set_rule(args.rule.clone());
+
+    if let Some(probe) = &args.probe {
+        map.set_item(parse_position(probe)?, MapItem::ProbeObstacle)?;
+    }
+
+    let commands = spawn_command_reader();
+
+    let mut delay_ms = args.delay_ms.clamp(MIN_DELAY_MS, MAX_DELAY_MS);
+    let mut paused = false;
+
+    let mut trail = HashMap::new();
+    let mut loop_positions = HashSet::new();
+    let mut status = String::from("starting patrol");
+
+    // Keep track of each guard's loop detection and whether they're still patrolling.
+    let mut detectors = map
+        .guards()
+        .iter()
+        .map(|guard| guard_map::LoopDetector::new(guard, map.rule()))
+        .collect::<Vec<_>>();
+    let mut active = (0..map.guards().len()).collect::<Vec<_>>();
+
+    print!("{}", CLEAR_SCREEN);
+    render(&map, &trail, &loop_positions, &status, delay_ms, paused)?;
+
+    let mut step = 0;
+    while !active.is_empty() {
+        // Figure out whether we should take a step this frame.
+        let mut should_step = !paused;
+        match commands.recv_timeout(Duration::from_millis(delay_ms)) {
+            Ok(Command::TogglePause) => {
+                paused = !paused;
+                should_step = false;
+            }
+            Ok(Command::Step) => should_step = paused,
+            Ok(Command::Faster) => delay_ms = (delay_ms / 2).max(MIN_DELAY_MS),
+            Ok(Command::Slower) => delay_ms = (delay_ms * 2).min(MAX_DELAY_MS),
+            Ok(Command::Quit) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                // If stdin is closed, nobody can unpause us, so just keep playing.
+                thread::sleep(Duration::from_millis(delay_ms));
+                paused = false;
+                should_step = true;
+            }
+        }
+
+        if !should_step {
+            render(&map, &trail, &loop_positions, &status, delay_ms, paused)?;
+            continue;
+        }
+
+        step += 1;
+
+        let mut messages = vec![];
+        let mut still_active = vec![];
+        for guard in active {
+            let event = match map.tick_guard(guard)? {
+                None => continue,
+                Some(event) => event,
+            };
+
+            record_trail(&mut trail, &event);
+
+            if let GuardEvent::LeftMap { .. } = event {
+                messages.push(format!("guard {} left the map", guard));
+                continue;
+            }
+
+            // Check if the guard has been in this state before; if so, highlight the loop and stop them.
+            if let Some(guard_map::Outcome::Looped {
+                cycle_start,
+                cycle_length,
+                states,
+            }) = detectors[guard].observe(&map.guards()[guard], map.rule())
+            {
+                loop_positions.extend(states.iter().map(|state| state.position));
+                messages.push(format!(
+                    "guard {} looped: length {} starting at step {}",
+                    guard, cycle_length, cycle_start
+                ));
+
+                continue;
+            }
+
+            messages.push(format!("{:?}", event));
+            still_active.push(guard);
+        }
+
+        active = still_active;
+        status = format!("step {}: {}", step, messages.join("; "));
+
+        render(&map, &trail, &loop_positions, &status, delay_ms, paused)?;
+    }
+
+    println!();
+
+    Ok(())
+}
+
+fn spawn_command_reader() -> mpsc::Receiver<Command> {
+    let (tx, rx) = mpsc::channel();
+
+    // Reading stdin blocks, so do it on another thread and hand commands back over a channel.
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            let command = match line.trim() {
+                "" | "n" => Command::Step,
+                "p" => Command::TogglePause,
+                "+" => Command::Faster,
+                "-" => Command::Slower,
+                "q" => Command::Quit,
+                _ => continue,
+            };
+
+            if tx.send(command).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+fn record_trail(trail: &mut HashMap<(usize, usize), Trail>, event: &GuardEvent) {
+    match event {
+        GuardEvent::Moved { from, to, .. } => {
+            // If x didn't change, the guard moved up or down.
+            let vertical = from.0 == to.0;
+
+            for pos in [from, to] {
+                let entry = trail.entry(*pos).or_default();
+                if vertical {
+                    entry.vertical = true;
+                } else {
+                    entry.horizontal = true;
+                }
+            }
+        }
+        GuardEvent::Turned { at, .. } => {
+            // Turning always crosses both axes, so it shows up as a `+`.
+            let entry = trail.entry(*at).or_default();
+            entry.vertical = true;
+            entry.horizontal = true;
+        }
+        GuardEvent::LeftMap { .. } => {}
+    }
+}
+
+fn render(
+    map: &guard_map::Map,
+    trail: &HashMap<(usize, usize), Trail>,
+    loop_positions: &HashSet<(usize, usize)>,
+    status: &str,
+    delay_ms: u64,
+    paused: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    let mut out = String::new();
+    out.push_str(CURSOR_HOME);
+
+    let guards = map
+        .guards()
+        .iter()
+        .filter_map(|guard| guard.position())
+        .map(|guard| (guard.position, guard.heading))
+        .collect::<HashMap<_, _>>();
+
+    let (width, height) = map.dimensions();
+    for y in 0..height {
+        for x in 0..width {
+            let (color, ch) = match map.get_item((x, y)) {
+                _ if guards.contains_key(&(x, y)) => (GUARD_COLOR, heading_char(&guards[&(x, y)])),
+                Some(MapItem::Obstacle) => ("", '#'),
+                Some(MapItem::ProbeObstacle) => (PROBE_COLOR, 'O'),
+                Some(MapItem::Free) | None => match trail.get(&(x, y)) {
+                    None => ("", '.'),
+                    Some(trail) => (
+                        if loop_positions.contains(&(x, y)) {
+                            LOOP_COLOR
+                        } else {
+                            TRAIL_COLOR
+                        },
+                        match (trail.vertical, trail.horizontal) {
+                            (true, true) => '+',
+                            (true, false) => '|',
+                            (false, true) => '-',
+                            (false, false) => '.',
+                        },
+                    ),
+                },
+            };
+
+            if color.is_empty() {
+                out.push(ch);
+            } else {
+                out.push_str(color);
+                out.push(ch);
+                out.push_str(RESET);
+            }
+        }
+
+        out.push_str(CLEAR_LINE);
+        out.push('\n');
+    }
+
+    out.push_str(&format!("\n{}{}\n", status, CLEAR_LINE));
+    out.push_str(&format!(
+        "{} | delay {}ms | [enter] step  p pause  + faster  - slower  q quit{}\n",
+        if paused { "paused" } else { "running" },
+        delay_ms,
+        CLEAR_LINE
+    ));
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(out.as_bytes())?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+fn heading_char(heading: &GuardHeading) -> char {
+    match heading {
+        GuardHeading::Up => '^',
+        GuardHeading::Right => '>',
+        GuardHeading::Down => 'v',
+        GuardHeading::Left => '<',
+    }
+}
+
+fn parse_position(position: &str) -> Result<(usize, usize), Box<dyn error::Error>> {
+    let (x, y) = position
+        .split_once(",")
+        .ok_or("expected probe position in the form x,y")?;
+
+    Ok((x.trim().parse()?, y.trim().parse()?))
+}
@@ -13,6 +13,8 @@ use part_two::part_two;
 
 mod solver;
 
+mod operators;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum DayPart {
     One,
@@ -30,6 +32,21 @@ struct Cli {
 
     #[arg(long = "debug", default_value_t = false)]
     debug: bool,
+
+    #[arg(long = "show", default_value_t = false)]
+    show: bool,
+
+    #[arg(long = "all", default_value_t = false)]
+    all: bool,
+
+    #[arg(long = "ops")]
+    ops: Option<String>,
+
+    #[arg(long = "order", value_enum, default_value_t = solver::EvalOrder::LeftToRight)]
+    order: solver::EvalOrder,
+
+    #[arg(long = "jobs")]
+    jobs: Option<usize>,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
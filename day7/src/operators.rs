@@ -0,0 +1,368 @@
+use std::{error, fmt};
+
+pub enum Inverse {
+    Impossible,
+    Exact(i64),
+
+    // Either of two lefts work (e.g. both roots of an even power).
+    Either(i64, i64),
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    // a - b - c is (a - b) - c.
+    Left,
+
+    // a ** b ** c is a ** (b ** c).
+    Right,
+}
+
+// An operator that can be placed between two numbers in an equation.
+//
+// Operators that can work out their left operand from a result and their right operand should implement `invert`
+// (and return true from `invertible`) so the solver can work backwards and prune impossible equations early.
+pub trait Operator: fmt::Debug + Send + Sync {
+    fn symbol(&self) -> &'static str;
+
+    // Higher precedence operators get evaluated first when evaluating with precedence.
+    fn precedence(&self) -> u8;
+
+    // Which side gets evaluated first when two operators with the same precedence are next to each other.
+    fn associativity(&self) -> Associativity {
+        Associativity::Left
+    }
+
+    // Returns None if the operation overflows or is otherwise undefined.
+    fn apply(&self, left: i64, right: i64) -> Option<i64>;
+
+    fn invertible(&self) -> bool {
+        false
+    }
+
+    // Whether applying the operator to two non-negative numbers always gives a non-negative number; if every operator
+    // does, the solver can throw away any branch that needs a negative result.
+    fn keeps_non_negative(&self) -> bool {
+        false
+    }
+
+    // Figures out what `left` has to be for `left <op> right` to equal `result`.
+    fn invert(&self, _result: i64, _right: i64) -> Inverse {
+        Inverse::Impossible
+    }
+}
+
+#[derive(Debug)]
+pub struct Add;
+
+impl Operator for Add {
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_add(right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn keeps_non_negative(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: i64, right: i64) -> Inverse {
+        match result.checked_sub(right) {
+            Some(left) => Inverse::Exact(left),
+            None => Inverse::Impossible,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Sub;
+
+impl Operator for Sub {
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_sub(right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: i64, right: i64) -> Inverse {
+        match result.checked_add(right) {
+            Some(left) => Inverse::Exact(left),
+            None => Inverse::Impossible,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Mul;
+
+impl Operator for Mul {
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn precedence(&self) -> u8 {
+        3
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_mul(right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn keeps_non_negative(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: i64, right: i64) -> Inverse {
+        match (result, right) {
+            // Anything times zero is zero.
+            (0, 0) => Inverse::Any,
+            (_, 0) => Inverse::Impossible,
+            // Dividing i64::MIN by -1 overflows (and so does the remainder).
+            _ => match (result.checked_rem(right), result.checked_div(right)) {
+                (Some(0), Some(left)) => Inverse::Exact(left),
+                _ => Inverse::Impossible,
+            },
+        }
+    }
+}
+
+// Integer division that rounds towards zero; since lots of lefts divide down to the same result, it isn't invertible.
+#[derive(Debug)]
+pub struct Div;
+
+impl Operator for Div {
+    fn symbol(&self) -> &'static str {
+        "/"
+    }
+
+    fn precedence(&self) -> u8 {
+        3
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_div(right)
+    }
+}
+
+#[derive(Debug)]
+pub struct Cat;
+
+impl Operator for Cat {
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+
+    fn precedence(&self) -> u8 {
+        1
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        // There's no sensible way to stick a negative number onto either side of another one.
+        if left < 0 || right < 0 {
+            return None;
+        }
+
+        left.checked_mul(10i64.checked_pow(num_digits(right))?)?
+            .checked_add(right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn keeps_non_negative(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: i64, right: i64) -> Inverse {
+        if result < 0 || right < 0 {
+            return Inverse::Impossible;
+        }
+
+        // Strip the digits of right off of the end of result (e.g. 156 = left || 6 means left = 15).
+        let divisor = match 10i64.checked_pow(num_digits(right)) {
+            Some(divisor) => divisor,
+            None => return Inverse::Impossible,
+        };
+
+        if result % divisor == right {
+            Inverse::Exact(result / divisor)
+        } else {
+            Inverse::Impossible
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Pow;
+
+impl Operator for Pow {
+    fn symbol(&self) -> &'static str {
+        "**"
+    }
+
+    fn precedence(&self) -> u8 {
+        4
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Right
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_pow(right.try_into().ok()?)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn keeps_non_negative(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: i64, right: i64) -> Inverse {
+        match right {
+            _ if right < 0 => Inverse::Impossible,
+            // Anything to the zeroth power is one.
+            0 if result == 1 => Inverse::Any,
+            0 => Inverse::Impossible,
+            _ => {
+                // `apply` can't raise anything to a power that doesn't fit in a u32, so neither can we.
+                let exp = match u32::try_from(right) {
+                    Ok(exp) => exp,
+                    Err(_) => return Inverse::Impossible,
+                };
+
+                // Find the integer root and check that it actually works.
+                let root = (result.unsigned_abs() as f64)
+                    .powf(1.0 / exp as f64)
+                    .round() as i64;
+                let root = if result < 0 { -root } else { root };
+
+                for candidate in [root - 1, root, root + 1] {
+                    if candidate.checked_pow(exp) == Some(result) {
+                        // Even powers have a negative root, too.
+                        if exp.is_multiple_of(2) && candidate != 0 {
+                            return Inverse::Either(candidate, -candidate);
+                        }
+
+                        return Inverse::Exact(candidate);
+                    }
+                }
+
+                Inverse::Impossible
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Xor;
+
+impl Operator for Xor {
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn precedence(&self) -> u8 {
+        0
+    }
+
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        Some(left ^ right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn invert(&self, result: i64, right: i64) -> Inverse {
+        Inverse::Exact(result ^ right)
+    }
+}
+
+// Parses a comma-delimited list of operator symbols (e.g. "+,*,||").
+pub fn parse(symbols: &str) -> Result<Vec<Box<dyn Operator>>, Box<dyn error::Error>> {
+    symbols
+        .split(",")
+        .map(
+            |symbol| -> Result<Box<dyn Operator>, Box<dyn error::Error>> {
+                Ok(match symbol.trim() {
+                    "+" => Box::new(Add),
+                    "-" => Box::new(Sub),
+                    "*" => Box::new(Mul),
+                    "/" => Box::new(Div),
+                    "||" => Box::new(Cat),
+                    "**" => Box::new(Pow),
+                    "^" => Box::new(Xor),
+                    symbol => {
+                        return Err(format!(
+                            "unexpected operator {} (expected one of +, -, *, /, ||, **, ^)",
+                            symbol
+                        )
+                        .into())
+                    }
+                })
+            },
+        )
+        .collect()
+}
+
+fn num_digits(num: i64) -> u32 {
+    match num.checked_ilog10() {
+        Some(log) => log + 1,
+        // Zero still has a digit!
+        None => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_invert_overflow_is_impossible() {
+        assert!(matches!(Mul.invert(i64::MIN, -1), Inverse::Impossible));
+        assert!(matches!(Mul.invert(-10, -2), Inverse::Exact(5)));
+    }
+
+    #[test]
+    fn pow_invert_finds_both_roots_of_even_powers() {
+        assert!(matches!(Pow.invert(9, 2), Inverse::Either(3, -3)));
+        assert!(matches!(Pow.invert(-27, 3), Inverse::Exact(-3)));
+        assert!(matches!(Pow.invert(0, 2), Inverse::Exact(0)));
+        assert!(matches!(Pow.invert(10, 2), Inverse::Impossible));
+    }
+
+    #[test]
+    fn pow_invert_agrees_with_apply_for_huge_exponents() {
+        assert_eq!(Pow.apply(0, 1 << 32), None);
+        assert!(matches!(Pow.invert(0, 1 << 32), Inverse::Impossible));
+        assert!(matches!(Pow.invert(1, 1 << 32), Inverse::Impossible));
+    }
+}
@@ -3,15 +3,13 @@ use std::{
     io::{self},
 };
 
-use crate::{solver, Cli};
+use crate::{operators, solver, Cli};
 
 pub fn part_one(
     args: &Cli,
     file_reader: io::BufReader<fs::File>,
 ) -> Result<(), Box<dyn error::Error>> {
-    solver::solve(
-        args,
-        file_reader,
-        &[solver::Operator::Add, solver::Operator::Mul],
-    )
+    let operators = operators::parse(args.ops.as_deref().unwrap_or("+,*"))?;
+
+    solver::solve(args, file_reader, &operators)
 }
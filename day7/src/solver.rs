@@ -1,40 +1,110 @@
 use std::{
-    error, fs,
+    error,
+    fmt::{self, Write},
+    fs,
     io::{self, BufRead},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
 };
 
-use crate::Cli;
+use clap::ValueEnum;
 
-pub fn solve(
-    _: &Cli,
+use crate::{
+    operators::{Associativity, Inverse, Operator},
+    Cli,
+};
+
+pub struct Equation {
+    pub total: i64,
+    pub nums: Vec<i64>,
+}
+
+pub fn parse_equations(
     file_reader: io::BufReader<fs::File>,
-    operators: &[Operator],
-) -> Result<(), Box<dyn error::Error>> {
-    let mut result = 0;
+) -> Result<Vec<Equation>, Box<dyn error::Error>> {
+    let mut equations = vec![];
 
     for line in file_reader.lines() {
         let line = line?;
 
-        let (eq_total, eq_nums) = {
-            let (total, right) = line
-                .split_once(":")
-                .ok_or("expected colon-delimited input line")?;
+        let (total, right) = line
+            .split_once(":")
+            .ok_or("expected colon-delimited input line")?;
 
-            let eq_nums = right[1..]
-                .split(" ")
-                .map(|num| {
-                    num.parse()
-                        .expect("expected numbers on right side of colon to be i64s")
-                })
-                .collect::<Vec<i64>>();
+        let nums = right
+            .split_whitespace()
+            .map(|num| num.parse())
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        if nums.is_empty() {
+            return Err(format!("expected numbers on right side of colon: {}", line).into());
+        }
+
+        equations.push(Equation {
+            total: total.parse()?,
+            nums,
+        });
+    }
+
+    Ok(equations)
+}
 
-            (total.parse::<i64>()?, eq_nums)
-        };
+pub fn solve(
+    args: &Cli,
+    file_reader: io::BufReader<fs::File>,
+    operators: &[Box<dyn Operator>],
+) -> Result<(), Box<dyn error::Error>> {
+    let equations = parse_equations(file_reader)?;
 
-        // Iterate over each permutation of operator placement for numbers and try to find one that solves the equation.
-        if try_solve(operators, eq_total, &eq_nums[1..], eq_nums[0]) {
-            result += eq_total;
+    let jobs = match args.jobs {
+        Some(0) => return Err("expected --jobs to be at least 1".into()),
+        Some(jobs) => jobs,
+        None => thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+    };
+
+    let all_solutions = solve_parallel(args, operators, &equations, jobs);
+
+    // Go through the results in input order so the output is the same no matter how the work got split up.
+    let mut result = 0i64;
+    for (equation, solutions) in equations.iter().zip(all_solutions) {
+        if solutions.is_empty() {
+            continue;
+        }
+
+        if args.show {
+            for solution in solutions.iter() {
+                println!(
+                    "{}",
+                    format_equation(equation.total, &equation.nums, solution)?
+                );
+            }
         }
+
+        // Double-check that the solutions we found actually work when evaluated.
+        if args.debug {
+            for solution in solutions.iter() {
+                if evaluate(&equation.nums, solution, args.order) != Some(equation.total) {
+                    return Err(format!(
+                        "solver found bad solution: {}",
+                        format_equation(equation.total, &equation.nums, solution)?
+                    )
+                    .into());
+                }
+            }
+        }
+
+        if args.all {
+            println!(
+                "{}: {} solution{}",
+                equation.total,
+                solutions.len(),
+                if solutions.len() == 1 { "" } else { "s" }
+            );
+        }
+
+        result = result
+            .checked_add(equation.total)
+            .ok_or("result overflowed i64")?;
     }
 
     println!("{}", result);
@@ -42,34 +112,372 @@ pub fn solve(
     Ok(())
 }
 
-fn try_solve(operators: &[Operator], expected: i64, nums: &[i64], acc: i64) -> bool {
-    match nums {
-        [] => return acc == expected,
-        [num, rest @ ..] => {
-            for op in operators {
-                if try_solve(operators, expected, rest, op.exec(&acc, num)) {
-                    return true;
+// Solves each equation on a pool of `jobs` workers, returning the solutions in the same order as the equations.
+fn solve_parallel<'a>(
+    args: &Cli,
+    operators: &'a [Box<dyn Operator>],
+    equations: &[Equation],
+    jobs: usize,
+) -> Vec<Vec<Vec<&'a dyn Operator>>> {
+    // Workers grab the next unsolved equation off of a shared counter so a few slow equations don't hold up
+    // everyone else.
+    let next = AtomicUsize::new(0);
+
+    let mut solved = thread::scope(|scope| {
+        let workers = (0..jobs.min(equations.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut solved = vec![];
+
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let equation = match equations.get(i) {
+                            Some(equation) => equation,
+                            None => break,
+                        };
+
+                        // Iterate over each permutation of operator placement for numbers and try to find one
+                        // (or all of them) that solves the equation.
+                        let solutions = if args.all {
+                            try_solve_all(operators, args.order, equation.total, &equation.nums)
+                        } else {
+                            try_solve(operators, args.order, equation.total, &equation.nums)
+                                .into_iter()
+                                .collect()
+                        };
+
+                        solved.push((i, solutions));
+                    }
+
+                    solved
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("solver worker panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    solved.sort_by_key(|(i, _)| *i);
+
+    solved.into_iter().map(|(_, solutions)| solutions).collect()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum EvalOrder {
+    LeftToRight,
+    Precedence,
+}
+
+// Finds the first placement of operators that solves the equation.
+pub fn try_solve<'a>(
+    operators: &'a [Box<dyn Operator>],
+    order: EvalOrder,
+    expected: i64,
+    nums: &[i64],
+) -> Option<Vec<&'a dyn Operator>> {
+    let mut search = Search::new(operators, order, nums, false);
+    search.run(expected);
+
+    search.solutions.pop()
+}
+
+// Finds every placement of operators that solves the equation.
+pub fn try_solve_all<'a>(
+    operators: &'a [Box<dyn Operator>],
+    order: EvalOrder,
+    expected: i64,
+    nums: &[i64],
+) -> Vec<Vec<&'a dyn Operator>> {
+    let mut search = Search::new(operators, order, nums, true);
+    search.run(expected);
+
+    search.solutions
+}
+
+struct Search<'a, 'n> {
+    operators: &'a [Box<dyn Operator>],
+    order: EvalOrder,
+    nums: &'n [i64],
+    find_all: bool,
+
+    // If every number is non-negative and none of the operators can make them negative, we can ignore any branch
+    // that needs a negative result.
+    non_negative: bool,
+
+    // The operators we've picked so far (from right to left when working backwards).
+    used: Vec<&'a dyn Operator>,
+    solutions: Vec<Vec<&'a dyn Operator>>,
+}
+
+impl<'a, 'n> Search<'a, 'n> {
+    fn new(
+        operators: &'a [Box<dyn Operator>],
+        order: EvalOrder,
+        nums: &'n [i64],
+        find_all: bool,
+    ) -> Self {
+        Search {
+            operators,
+            order,
+            nums,
+            find_all,
+            non_negative: nums.iter().all(|num| *num >= 0)
+                && operators.iter().all(|op| op.keeps_non_negative()),
+            used: vec![],
+            solutions: vec![],
+        }
+    }
+
+    fn run(&mut self, expected: i64) {
+        // We can only work backwards if we're evaluating left-to-right and we know how to undo every operator.
+        let can_invert =
+            self.order == EvalOrder::LeftToRight && self.operators.iter().all(|op| op.invertible());
+
+        if can_invert {
+            self.run_backwards(expected, self.nums.len());
+        } else {
+            self.run_forwards(expected);
+        }
+    }
+
+    // Works backwards from the expected total: for the last number in the equation, each operator tells us what
+    // the rest of the equation would have to evaluate to (e.g. `a + 5 = 12` means `a = 7`). If there's no such
+    // value (e.g. `a * 5 = 12`), there's no point in looking at any of the numbers before it.
+    //
+    // Returns true if we're done searching.
+    fn run_backwards(&mut self, expected: i64, len: usize) -> bool {
+        if self.non_negative && expected < 0 {
+            return false;
+        }
+
+        if len == 1 {
+            if self.nums[0] == expected {
+                return self.found(true);
+            }
+
+            return false;
+        }
+
+        let right = self.nums[len - 1];
+        for op in self.operators {
+            self.used.push(op.as_ref());
+
+            let done = match op.invert(expected, right) {
+                Inverse::Impossible => false,
+                Inverse::Exact(left) => self.run_backwards(left, len - 1),
+                Inverse::Either(left, other_left) => {
+                    self.run_backwards(left, len - 1) || self.run_backwards(other_left, len - 1)
                 }
+                Inverse::Any => self.run_any(len - 1),
+            };
+
+            self.used.pop();
+
+            if done {
+                return true;
             }
         }
+
+        false
     }
 
-    return false;
+    // Every placement of operators for the first `len` numbers works, so record them all.
+    //
+    // Nothing before this point got evaluated though, so make sure the whole equation doesn't overflow first.
+    fn run_any(&mut self, len: usize) -> bool {
+        if len == 1 {
+            let operators = self.used.iter().rev().copied().collect::<Vec<_>>();
+            if evaluate(self.nums, &operators, self.order).is_none() {
+                return false;
+            }
+
+            return self.found(true);
+        }
+
+        for op in self.operators {
+            self.used.push(op.as_ref());
+            let done = self.run_any(len - 1);
+            self.used.pop();
+
+            if done {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Tries every placement of operators and evaluates the whole equation for each of them.
+    fn run_forwards(&mut self, expected: i64) -> bool {
+        if self.used.len() == self.nums.len() - 1 {
+            if evaluate(self.nums, &self.used, self.order) == Some(expected) {
+                return self.found(false);
+            }
+
+            return false;
+        }
+
+        for op in self.operators {
+            self.used.push(op.as_ref());
+            let done = self.run_forwards(expected);
+            self.used.pop();
+
+            if done {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn found(&mut self, reversed: bool) -> bool {
+        let mut solution = self.used.clone();
+        if reversed {
+            solution.reverse();
+        }
+
+        self.solutions.push(solution);
+
+        !self.find_all
+    }
 }
 
-#[derive(Debug)]
-pub enum Operator {
-    Add,
-    Mul,
-    Cat,
+// Evaluates the equation, returning None if it overflows (or is otherwise undefined).
+pub fn evaluate(nums: &[i64], operators: &[&dyn Operator], order: EvalOrder) -> Option<i64> {
+    match order {
+        EvalOrder::LeftToRight => operators
+            .iter()
+            .zip(&nums[1..])
+            .try_fold(nums[0], |acc, (op, num)| op.apply(acc, *num)),
+        EvalOrder::Precedence => {
+            // Keep a stack of values and a stack of operators waiting on their right side; whenever we see an
+            // operator that binds less tightly than the one on top of the stack, the one on top can be applied.
+            //
+            // Right-associative operators bind less tightly than themselves, so they have to wait for whatever's on
+            // their right.
+            let mut values = vec![nums[0]];
+            let mut pending: Vec<&dyn Operator> = vec![];
+
+            for (op, num) in operators.iter().zip(&nums[1..]) {
+                while let Some(top) = pending.last() {
+                    let applies_first = match op.associativity() {
+                        Associativity::Left => top.precedence() >= op.precedence(),
+                        Associativity::Right => top.precedence() > op.precedence(),
+                    };
+
+                    if !applies_first {
+                        break;
+                    }
+
+                    let right = values.pop()?;
+                    let left = values.pop()?;
+                    values.push(top.apply(left, right)?);
+                    pending.pop();
+                }
+
+                pending.push(*op);
+                values.push(*num);
+            }
+
+            while let Some(top) = pending.pop() {
+                let right = values.pop()?;
+                let left = values.pop()?;
+                values.push(top.apply(left, right)?);
+            }
+
+            values.pop()
+        }
+    }
+}
+
+pub fn format_equation(
+    total: i64,
+    nums: &[i64],
+    operators: &[&dyn Operator],
+) -> Result<String, fmt::Error> {
+    let mut equation = String::new();
+    write!(equation, "{} = {}", total, nums[0])?;
+
+    for (op, num) in operators.iter().zip(&nums[1..]) {
+        write!(equation, " {} {}", op.symbol(), num)?;
+    }
+
+    Ok(equation)
 }
 
-impl Operator {
-    pub fn exec(&self, left: &i64, right: &i64) -> i64 {
-        match self {
-            Operator::Add => left + right,
-            Operator::Mul => left * right,
-            Operator::Cat => format!("{}{}", left, right).parse().unwrap(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::{Add, Cat, Mul, Pow, Sub};
+
+    #[test]
+    fn precedence_evaluates_powers_right_to_left() {
+        assert_eq!(
+            evaluate(&[2, 3, 2], &[&Pow, &Pow], EvalOrder::Precedence),
+            Some(512)
+        );
+        assert_eq!(
+            evaluate(&[2, 3, 2], &[&Pow, &Pow], EvalOrder::LeftToRight),
+            Some(64)
+        );
+    }
+
+    #[test]
+    fn precedence_evaluates_everything_else_left_to_right() {
+        assert_eq!(
+            evaluate(&[10, 4, 3], &[&Sub, &Sub], EvalOrder::Precedence),
+            Some(3)
+        );
+        assert_eq!(
+            evaluate(
+                &[1, 2, 3, 2, 2],
+                &[&Add, &Mul, &Pow, &Sub],
+                EvalOrder::Precedence
+            ),
+            Some(17)
+        );
+    }
+
+    #[test]
+    fn any_placement_still_checks_for_overflow() {
+        let operators: Vec<Box<dyn Operator>> = vec![Box::new(Add), Box::new(Mul)];
+
+        assert!(try_solve(&operators, EvalOrder::LeftToRight, 0, &[i64::MAX, 2, 0]).is_none());
+        assert_eq!(
+            try_solve_all(&operators, EvalOrder::LeftToRight, 0, &[3, 2, 0]).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn backwards_and_forwards_agree_on_subtraction_and_concatenation() {
+        let operators: Vec<Box<dyn Operator>> = vec![Box::new(Sub), Box::new(Cat)];
+
+        for nums in [[1, 2, 5], [5, 2, 1], [0, 3, 12], [12, 15, 3]] {
+            // Every result any placement of operators could give.
+            let mut results = vec![];
+            for placement in 0..4 {
+                let ops = (0..2)
+                    .map(|i| operators[(placement >> i) & 1].as_ref())
+                    .collect::<Vec<_>>();
+
+                results.extend(evaluate(&nums, &ops, EvalOrder::LeftToRight));
+            }
+
+            for expected in -20..200 {
+                let solution = try_solve(&operators, EvalOrder::LeftToRight, expected, &nums);
+
+                assert_eq!(
+                    solution.is_some(),
+                    results.contains(&expected),
+                    "{}: {:?}",
+                    expected,
+                    nums
+                );
+            }
         }
     }
 }
@@ -1,8 +1,49 @@
-use std::{collections::HashMap, error, io::BufRead};
+use std::{
+    collections::{HashMap, HashSet},
+    error,
+    io::BufRead,
+    str::FromStr,
+};
 
 pub enum GridItem {
     Empty,
-    Antenna { _freq: char },
+    Antenna { freq: char },
+
+    // Examples mark antinodes with `#`, so keep track of them to compare against.
+    Antinode,
+}
+
+// Which multiples of the distance between two antennas produce antinodes.
+//
+// For a pair of antennas a and b, the kth harmonic is at a + k(a - b) (and likewise on b's side), so k=1 gives
+// the antinodes from part one. `All` gives the resonant harmonics from part two: every grid point on the line
+// through the antennas, including ones that fall between whole multiples of the distance.
+#[derive(Clone)]
+pub enum Harmonics {
+    First,
+    All,
+    Custom(Vec<i32>),
+}
+
+impl FromStr for Harmonics {
+    type Err = String;
+
+    // Parses "first", "all" or a comma-delimited list of harmonics (e.g. "1,2,3").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Harmonics::First),
+            "all" => Ok(Harmonics::All),
+            _ => s
+                .split(",")
+                .map(|k| {
+                    k.trim()
+                        .parse()
+                        .map_err(|_| format!("unexpected harmonic {}", k))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Harmonics::Custom),
+        }
+    }
 }
 
 pub struct Grid {
@@ -14,7 +55,7 @@ pub struct Grid {
 impl Grid {
     pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, Box<dyn error::Error>> {
         let mut rows = vec![];
-        let mut antennas = HashMap::new();
+        let mut antennas: HashMap<char, Vec<(usize, usize)>> = HashMap::new();
 
         for (y, line) in reader.lines().enumerate() {
             let line = line?;
@@ -24,17 +65,11 @@ impl Grid {
             for (x, ch) in line.chars().enumerate() {
                 match ch {
                     '.' => row.push(GridItem::Empty),
-                    freq @ _ => {
-                        match antennas.get_mut(&freq) {
-                            None => {
-                                antennas.insert(freq, vec![(x, y)]);
-                            }
-                            Some(antennas) => {
-                                antennas.push((x, y));
-                            }
-                        }
-
-                        row.push(GridItem::Antenna { _freq: freq });
+                    '#' => row.push(GridItem::Antinode),
+                    freq => {
+                        antennas.entry(freq).or_default().push((x, y));
+
+                        row.push(GridItem::Antenna { freq });
                     }
                 }
             }
@@ -52,4 +87,173 @@ impl Grid {
         (loc.0 >= 0 && loc.0 < self.grid[0].len() as i32)
             && (loc.1 >= 0 && loc.1 < self.grid.len() as i32)
     }
+
+    // Returns the antinodes marked in the input (if any).
+    pub fn marked_antinodes(&self) -> HashSet<(i32, i32)> {
+        let mut marked = HashSet::new();
+
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                if let GridItem::Antinode = item {
+                    marked.insert((x as i32, y as i32));
+                }
+            }
+        }
+
+        marked
+    }
+
+    // Draws the grid with a `#` at each antinode; antennas win out over antinodes so they stay visible.
+    pub fn render(&self, antinodes: &HashSet<(i32, i32)>) -> String {
+        let mut out = String::new();
+
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, item) in row.iter().enumerate() {
+                out.push(match item {
+                    GridItem::Antenna { freq } => *freq,
+                    _ if antinodes.contains(&(x as i32, y as i32)) => '#',
+                    _ => '.',
+                });
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+
+    // Finds the antinodes formed by each pair of same-frequency antennas, grouped by frequency.
+    pub fn antinodes(&self, harmonics: &Harmonics) -> HashMap<char, HashSet<(i32, i32)>> {
+        let mut antinodes = HashMap::new();
+
+        for (freq, antennas) in self.antennas.iter() {
+            let freq_antinodes: &mut HashSet<(i32, i32)> = antinodes.entry(*freq).or_default();
+
+            for (i, antenna_a) in antennas.iter().enumerate() {
+                let antenna_a = (antenna_a.0 as i32, antenna_a.1 as i32);
+
+                for antenna_b in &antennas[i + 1..] {
+                    let antenna_b = (antenna_b.0 as i32, antenna_b.1 as i32);
+
+                    // walk away from each antenna in the direction opposite the other one.
+                    //
+                    // we're assuming here that we're in a top-left (vs bottom-left) orientation here.
+                    for (from, to) in [(antenna_a, antenna_b), (antenna_b, antenna_a)] {
+                        let distance = (from.0 - to.0, from.1 - to.1);
+
+                        self.add_harmonics(freq_antinodes, from, distance, harmonics);
+                    }
+                }
+            }
+        }
+
+        antinodes
+    }
+
+    fn add_harmonics(
+        &self,
+        antinodes: &mut HashSet<(i32, i32)>,
+        from: (i32, i32),
+        distance: (i32, i32),
+        harmonics: &Harmonics,
+    ) {
+        let harmonic = |k: i32| (from.0 + k * distance.0, from.1 + k * distance.1);
+
+        match harmonics {
+            Harmonics::First => {
+                if self.is_valid_location(harmonic(1)) {
+                    antinodes.insert(harmonic(1));
+                }
+            }
+            Harmonics::All => {
+                // every grid point on the line through the antennas is an antinode, not just the ones a whole
+                // multiple of the distance away. e.g. for a distance of (2, 4), there's a point on the line
+                // every (1, 2), so step by the distance divided by the gcd of its components.
+                let divisor = gcd(distance.0, distance.1).max(1);
+                let step = (distance.0 / divisor, distance.1 / divisor);
+
+                // walk the line in both directions from the antenna until we fall off the grid (which also
+                // covers any points in between the two antennas).
+                for step in [step, (-step.0, -step.1)] {
+                    let mut curr_loc = from;
+                    while self.is_valid_location(curr_loc) {
+                        antinodes.insert(curr_loc);
+
+                        curr_loc.0 += step.0;
+                        curr_loc.1 += step.1;
+                    }
+                }
+            }
+            Harmonics::Custom(ks) => {
+                for k in ks {
+                    if self.is_valid_location(harmonic(*k)) {
+                        antinodes.insert(harmonic(*k));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_antinodes(grid: &str, freq: char) -> HashSet<(i32, i32)> {
+        let grid = Grid::from_reader(grid.as_bytes()).unwrap();
+
+        grid.antinodes(&Harmonics::All).remove(&freq).unwrap()
+    }
+
+    #[test]
+    fn all_harmonics_include_points_between_antennas() {
+        // The a antennas are (2, 4) apart.
+        let antinodes = all_antinodes(include_str!("../input.test.2"), 'a');
+
+        assert!(antinodes.contains(&(2, 3)));
+        assert_eq!(
+            antinodes,
+            HashSet::from([(1, 1), (2, 3), (3, 5), (4, 7), (5, 9)])
+        );
+    }
+
+    #[test]
+    fn all_harmonics_step_by_reduced_distance() {
+        // The antennas are (3, 6) apart, so there's a point every (1, 2).
+        let antinodes = all_antinodes(
+            "\
+a....
+.....
+.....
+.....
+.....
+.....
+...a.
+",
+            'a',
+        );
+
+        assert_eq!(antinodes, HashSet::from([(0, 0), (1, 2), (2, 4), (3, 6)]));
+    }
+
+    #[test]
+    fn all_harmonics_handle_horizontal_and_vertical_pairs() {
+        let horizontal = all_antinodes(".a..a.\n......\n", 'a');
+        assert_eq!(
+            horizontal,
+            HashSet::from([(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)])
+        );
+
+        let vertical = all_antinodes("a.\n..\na.\n", 'a');
+        assert_eq!(vertical, HashSet::from([(0, 0), (0, 1), (0, 2)]));
+    }
 }
@@ -13,6 +13,8 @@ use part_two::part_two;
 
 mod grid;
 
+mod report;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum DayPart {
     One,
@@ -30,6 +32,15 @@ struct Cli {
 
     #[arg(long = "debug", default_value_t = false)]
     debug: bool,
+
+    #[arg(long = "harmonics")]
+    harmonics: Option<grid::Harmonics>,
+
+    #[arg(long = "render", default_value_t = false)]
+    render: bool,
+
+    #[arg(long = "stats", default_value_t = false)]
+    stats: bool,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
@@ -0,0 +1,75 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{grid::Grid, Cli};
+
+// Prints whichever extra output was asked for and returns the total number of unique antinodes.
+pub fn report(args: &Cli, grid: &Grid, antinodes: &HashMap<char, HashSet<(i32, i32)>>) -> usize {
+    let all_antinodes = antinodes
+        .values()
+        .flatten()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    if args.render {
+        println!("{}", grid.render(&all_antinodes));
+    }
+
+    if args.stats {
+        print_stats(grid, antinodes);
+        println!();
+    }
+
+    // If the input came with antinodes marked, check that we found the same ones.
+    let marked = grid.marked_antinodes();
+    if args.debug && !marked.is_empty() {
+        let antennas = grid
+            .antennas
+            .values()
+            .flatten()
+            .map(|antenna| (antenna.0 as i32, antenna.1 as i32))
+            .collect::<HashSet<_>>();
+
+        let missing = marked.difference(&all_antinodes).count();
+
+        // Antinodes on top of antennas can't be marked, so don't count them as extras.
+        let extra = all_antinodes
+            .iter()
+            .filter(|antinode| !marked.contains(antinode) && !antennas.contains(antinode))
+            .count();
+
+        println!(
+            "compared to marked antinodes: {} missing, {} extra",
+            missing, extra
+        );
+    }
+
+    all_antinodes.len()
+}
+
+fn print_stats(grid: &Grid, antinodes: &HashMap<char, HashSet<(i32, i32)>>) {
+    let mut freqs = grid.antennas.keys().copied().collect::<Vec<_>>();
+    freqs.sort();
+
+    println!("freq  antennas  antinodes  overlaps");
+    for freq in freqs {
+        let freq_antinodes = &antinodes[&freq];
+
+        // Count the antinodes this frequency shares with any other frequency.
+        let overlaps = freq_antinodes
+            .iter()
+            .filter(|antinode| {
+                antinodes.iter().any(|(other, other_antinodes)| {
+                    *other != freq && other_antinodes.contains(antinode)
+                })
+            })
+            .count();
+
+        println!(
+            "{:<4}  {:>8}  {:>9}  {:>8}",
+            freq,
+            grid.antennas[&freq].len(),
+            freq_antinodes.len(),
+            overlaps
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use std::{
+    cmp::{self, Reverse},
+    collections::{BTreeMap, BinaryHeap, VecDeque},
+    error, fs, io,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    diff,
+    disk_map::{self, DiskMap, FileSpan, FreeSpaceRegion},
+    Cli,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum StrategyKind {
+    Block,
+    FirstFit,
+    BestFit,
+    WorstFit,
+    Defrag,
+}
+
+impl StrategyKind {
+    pub fn strategy(&self) -> Box<dyn CompactionStrategy> {
+        match self {
+            StrategyKind::Block => Box::new(BlockStrategy),
+            StrategyKind::FirstFit => Box::new(FileStrategy(Fit::First)),
+            StrategyKind::BestFit => Box::new(FileStrategy(Fit::Best)),
+            StrategyKind::WorstFit => Box::new(FileStrategy(Fit::Worst)),
+            StrategyKind::Defrag => Box::new(DefragStrategy),
+        }
+    }
+}
+
+pub trait CompactionStrategy {
+    // Compacts the disk, returning how many moves it took (whatever a "move" is for the strategy).
+    fn compact(&self, args: &Cli, disk: &mut DiskMap) -> usize;
+}
+
+pub fn run(
+    args: &Cli,
+    file_reader: io::BufReader<fs::File>,
+    default_strategy: StrategyKind,
+) -> Result<(), Box<dyn error::Error>> {
+    let disk = disk_map::DiskMap::from_reader(file_reader)?;
+
+    // If we're reporting, try every strategy on its own copy of the disk and compare them.
+    if args.report {
+        println!("strategy   checksum              gaps  fragmented  moves");
+
+        for kind in StrategyKind::value_variants() {
+            let mut disk = disk.clone();
+            let moves = kind.strategy().compact(args, &mut disk);
+            let fragmentation = disk.fragmentation();
+
+            println!(
+                "{:<9}  {:<20}  {:>4}  {:>10}  {:>5}",
+                kind.to_possible_value().unwrap().get_name(),
+                disk.checksum(),
+                fragmentation.gaps,
+                fragmentation.fragmented_files,
+                moves
+            );
+        }
+
+        println!();
+    }
+
+    let original = disk.clone();
+
+    let mut disk = disk;
+    args.strategy
+        .unwrap_or(default_strategy)
+        .strategy()
+        .compact(args, &mut disk);
+
+    if args.diff {
+        println!("{}", diff::render(&original, &disk, args.width));
+    }
+
+    // If we're encoding, write the compacted disk back out and make sure it reads back in as the same disk.
+    if args.encode {
+        let encoded = disk.encode();
+        print!("{}", encoded);
+
+        let decoded = DiskMap::from_reader(encoded.as_bytes())?;
+
+        let spans = |disk: &DiskMap| {
+            disk.merged_spans()
+                .iter()
+                .map(|span| (span.id, span.start, span.size))
+                .collect::<Vec<_>>()
+        };
+
+        if spans(&decoded) != spans(&disk)
+            || decoded.size != disk.size
+            || decoded.checksum() != disk.checksum()
+        {
+            return Err("encoded disk map didn't decode to the same disk".into());
+        }
+
+        println!();
+    }
+
+    println!("{}", disk.checksum());
+
+    Ok(())
+}
+
+// Moves blocks one at a time from the end of the disk into the leftmost free space; each block moved is a move.
+pub struct BlockStrategy;
+
+impl CompactionStrategy for BlockStrategy {
+    fn compact(&self, args: &Cli, disk: &mut DiskMap) -> usize {
+        let mut moves = 0;
+
+        // Rather than moving individual blocks, fill each gap (from left to right) with as many blocks as we can
+        // take off of the end of the last span at once.
+        let mut spans = disk
+            .sorted_spans()
+            .into_iter()
+            .cloned()
+            .collect::<VecDeque<_>>();
+
+        let mut compacted = vec![];
+        let mut next_start = 0;
+
+        while let Some(mut span) = spans.pop_front() {
+            while next_start < span.start {
+                let gap = span.start - next_start;
+
+                match spans.back_mut() {
+                    Some(last) => {
+                        let size = cmp::min(gap, last.size);
+
+                        compacted.push(FileSpan {
+                            id: last.id,
+                            start: next_start,
+                            size,
+                        });
+
+                        last.size -= size;
+                        if last.size == 0 {
+                            spans.pop_back();
+                        }
+
+                        next_start += size;
+                        moves += size;
+                    }
+                    None => {
+                        // This is the last span, so its own blocks fill in the gap (which means it ends up
+                        // starting right where the gap started).
+                        moves += cmp::min(gap, span.size);
+                        span.start = next_start;
+                    }
+                }
+            }
+
+            next_start = span.start + span.size;
+            compacted.push(span);
+
+            if args.debug {
+                println!(
+                    "{}",
+                    DiskMap {
+                        spans: compacted.iter().chain(spans.iter()).cloned().collect(),
+                        size: disk.size,
+                    }
+                );
+            }
+        }
+
+        disk.spans = compacted;
+
+        moves
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Fit {
+    // The leftmost region that fits.
+    First,
+
+    // The smallest region that fits.
+    Best,
+
+    // The biggest region.
+    Worst,
+}
+
+// Moves each file (in order of decreasing id) into a free region to the left of it that can fit the whole file;
+// each file moved is a move.
+pub struct FileStrategy(pub Fit);
+
+impl CompactionStrategy for FileStrategy {
+    fn compact(&self, args: &Cli, disk: &mut DiskMap) -> usize {
+        let mut free_space = FreeSpaceIndex::new(&disk.free_space_regions());
+        let mut moves = 0;
+
+        // Go through whole files (not the chunks they might've been read in as) from the highest id down; files
+        // aren't necessarily stored in id order if the disk came with a line of ids.
+        disk.spans = disk.merged_spans();
+        disk.spans
+            .sort_by_key(|span| (Reverse(span.id), Reverse(span.start)));
+
+        for i in 0..disk.spans.len() {
+            let span = &mut disk.spans[i];
+
+            let region_start = match free_space.allocate(span.size, span.start, self.0) {
+                None => continue,
+                Some(region_start) => region_start,
+            };
+
+            // Moving the file is just a matter of changing where it starts.
+            span.start = region_start;
+            moves += 1;
+
+            if args.debug {
+                println!("{}", &disk);
+            }
+        }
+
+        moves
+    }
+}
+
+// Slides every file as far left as it'll go (keeping the files in the same order) so there's no free space
+// between any of them; each file that had to move is a move.
+pub struct DefragStrategy;
+
+impl CompactionStrategy for DefragStrategy {
+    fn compact(&self, args: &Cli, disk: &mut DiskMap) -> usize {
+        let mut moves = 0;
+        let mut next_start = 0;
+
+        disk.spans.sort_by_key(|span| span.start);
+
+        for i in 0..disk.spans.len() {
+            let span = &mut disk.spans[i];
+
+            if span.start != next_start {
+                span.start = next_start;
+                moves += 1;
+
+                if args.debug {
+                    println!("{}", &disk);
+                }
+            }
+
+            next_start = disk.spans[i].start + disk.spans[i].size;
+        }
+
+        moves
+    }
+}
+
+// Keeps a min-heap of free region starts for each region length so we can find a region that fits a file by
+// checking (at most) one region per length.
+//
+// Free regions usually fit in a single digit, but they don't have to: an empty file between two runs of free
+// space turns them into one bigger region.
+pub struct FreeSpaceIndex {
+    heaps: BTreeMap<usize, BinaryHeap<Reverse<usize>>>,
+}
+
+impl FreeSpaceIndex {
+    pub fn new(free_space_regions: &[FreeSpaceRegion]) -> Self {
+        let mut index = FreeSpaceIndex {
+            heaps: BTreeMap::new(),
+        };
+
+        for region in free_space_regions {
+            index.add(region.start, region.size);
+        }
+
+        index
+    }
+
+    fn add(&mut self, start: usize, size: usize) {
+        if size > 0 {
+            self.heaps.entry(size).or_default().push(Reverse(start));
+        }
+    }
+
+    // Claims `size` blocks at the start of a region that can fit them and starts before `before`, returning
+    // where that region starts.
+    pub fn allocate(&mut self, size: usize, before: usize, fit: Fit) -> Option<usize> {
+        // The leftmost region of each length is the only one of that length we'd ever pick.
+        let mut candidates = self
+            .heaps
+            .range(size..)
+            .filter_map(|(len, heap)| heap.peek().map(|Reverse(start)| (*start, *len)))
+            .filter(|(start, _)| *start < before);
+
+        let (_, region_len) = match fit {
+            Fit::First => candidates.min()?,
+            Fit::Best => candidates.next()?,
+            Fit::Worst => candidates.next_back()?,
+        };
+
+        let heap = self.heaps.get_mut(&region_len)?;
+        let Reverse(start) = heap.pop()?;
+
+        // Don't leave empty heaps around for the next search to wade through.
+        if heap.is_empty() {
+            self.heaps.remove(&region_len);
+        }
+
+        // Whatever's left of the region is now a smaller region.
+        self.add(start + size, region_len - size);
+
+        Some(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn compact(disk: &str, kind: StrategyKind) -> DiskMap {
+        let args = Cli::parse_from(["day9", "--part", "two"]);
+
+        let mut disk = DiskMap::from_reader(disk.as_bytes()).unwrap();
+        kind.strategy().compact(&args, &mut disk);
+
+        disk
+    }
+
+    #[test]
+    fn compacts_free_regions_longer_than_a_digit() {
+        // The empty files in the middle make one 27 block region between the two files.
+        for kind in [
+            StrategyKind::FirstFit,
+            StrategyKind::BestFit,
+            StrategyKind::WorstFit,
+        ] {
+            let disk = compact("1909091", kind);
+
+            assert_eq!(disk.free_space_regions().len(), 1);
+            assert_eq!(disk.checksum(), 1);
+        }
+    }
+
+    #[test]
+    fn best_and_worst_fit_pick_by_region_length() {
+        // A 12 block region (split by an empty file), a 1 block file, a 2 block region, then a 2 block file.
+        let best = compact("1903122", StrategyKind::BestFit);
+        let worst = compact("1903122", StrategyKind::WorstFit);
+
+        assert_eq!(
+            best.spans.iter().find(|span| span.id == 2).unwrap().start,
+            14
+        );
+        assert_eq!(
+            worst.spans.iter().find(|span| span.id == 2).unwrap().start,
+            1
+        );
+    }
+
+    #[test]
+    fn moves_files_by_id_not_position() {
+        // File 2 comes before file 1 on the disk, but should still get moved first.
+        let disk = compact("13112\n0 2 1", StrategyKind::FirstFit);
+        assert_eq!(disk.checksum(), 7);
+
+        // File 1 was split into two spans when it was encoded, but it's still one 12 block file that doesn't fit.
+        let disk = compact("15903\n0 1 1", StrategyKind::FirstFit);
+        assert_eq!(disk.checksum(), 138);
+    }
+
+    #[test]
+    fn recompacts_encoded_disks() {
+        for kind in StrategyKind::value_variants() {
+            let disk = compact("2333133121414131402", *kind);
+            let encoded = disk.encode();
+
+            let decoded = DiskMap::from_reader(encoded.as_bytes()).unwrap();
+            assert_eq!(decoded.checksum(), disk.checksum());
+
+            // Compacting an already compacted disk shouldn't blow up.
+            for kind in StrategyKind::value_variants() {
+                let mut recompacted = decoded.clone();
+                kind.strategy().compact(
+                    &Cli::parse_from(["day9", "--part", "two"]),
+                    &mut recompacted,
+                );
+            }
+        }
+    }
+}
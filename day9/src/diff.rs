@@ -0,0 +1,74 @@
+use std::fmt::Write;
+
+use crate::disk_map::DiskMap;
+
+// Renders the disk before and after compacting side by side, `width` blocks to a row.
+//
+// Each file gets its own color (and shows the last digit of its id); blocks that moved are underlined on the
+// after side, and the spots they were moved out of are dimmed on the before side.
+pub fn render(before: &DiskMap, after: &DiskMap, width: usize) -> String {
+    let before_blocks = blocks(before);
+    let after_blocks = blocks(after);
+
+    let mut out = String::new();
+    let mut moved_blocks = 0;
+
+    for row_start in (0..before_blocks.len()).step_by(width) {
+        let row_end = (row_start + width).min(before_blocks.len());
+
+        write!(out, "{:>8}  ", row_start).unwrap();
+
+        for i in row_start..row_end {
+            let vacated = before_blocks[i].is_some() && before_blocks[i] != after_blocks[i];
+            write_block(&mut out, before_blocks[i], if vacated { "2" } else { "" });
+        }
+
+        // Pad out short rows so the after side still lines up.
+        write!(out, "{}  |  ", " ".repeat(width - (row_end - row_start))).unwrap();
+
+        for i in row_start..row_end {
+            let moved = after_blocks[i].is_some() && before_blocks[i] != after_blocks[i];
+            if moved {
+                moved_blocks += 1;
+            }
+
+            write_block(&mut out, after_blocks[i], if moved { "4" } else { "" });
+        }
+
+        out.push('\n');
+    }
+
+    writeln!(out, "\n{} blocks moved", moved_blocks).unwrap();
+
+    out
+}
+
+// Lays the disk out block by block, with the id of the file in each block (if there is one).
+fn blocks(disk: &DiskMap) -> Vec<Option<u32>> {
+    let mut blocks = vec![None; disk.size];
+
+    for span in &disk.spans {
+        for block in &mut blocks[span.start..span.start + span.size] {
+            *block = Some(span.id);
+        }
+    }
+
+    blocks
+}
+
+fn write_block(out: &mut String, block: Option<u32>, style: &str) {
+    match block {
+        None => out.push('.'),
+        Some(id) => {
+            // Skip the first 16 (and the darkest) colors of the 256-color palette so every file stays readable;
+            // multiplying by a prime spreads neighboring ids out so they don't end up looking alike.
+            let color = 22 + (id as usize * 37) % 210;
+
+            if style.is_empty() {
+                write!(out, "\x1b[38;5;{}m{}\x1b[0m", color, id % 10).unwrap();
+            } else {
+                write!(out, "\x1b[{};38;5;{}m{}\x1b[0m", style, color, id % 10).unwrap();
+            }
+        }
+    }
+}
@@ -1,100 +1,260 @@
 use core::fmt;
-use std::{error, fmt::Write, io::BufRead};
+use std::{collections::HashMap, error, fmt::Write, io::BufRead};
 
-#[derive(Debug)]
-pub enum DiskMapEntry {
-    FileBlock(u32),
-    FreeSpace,
-}
-
-#[derive(Debug)]
-pub struct DiskMap {
-    pub disk: Vec<DiskMapEntry>,
-}
-
-#[derive(Debug)]
-pub struct NewDiskMap {
-    pub disk_map: DiskMap,
-
-    pub blocks: Vec<BlockInfo>,
-    pub free_space_regions: Vec<FreeSpaceRegion>,
-}
-
-#[derive(Debug)]
-pub struct BlockInfo {
+// A run of contiguous blocks that all belong to the same file.
+//
+// Files start out as a single span, but compacting block-by-block can split a file up into several of them.
+#[derive(Debug, Clone)]
+pub struct FileSpan {
+    pub id: u32,
     pub start: usize,
-    pub size: u32,
+    pub size: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FreeSpaceRegion {
     pub start: usize,
-    pub size: u32,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiskMap {
+    pub spans: Vec<FileSpan>,
+    pub size: usize,
 }
 
 impl DiskMap {
-    pub fn from_reader<R: BufRead>(reader: R) -> Result<NewDiskMap, Box<dyn error::Error>> {
-        let mut disk = vec![];
-        let mut blocks = vec![];
-        let mut free_space_regions = vec![];
+    // Reads a dense disk map (e.g. 2333133121414131402), where digits alternate between file and free space sizes.
+    //
+    // Files get ids in the order they appear, unless there's a second line listing the id of each (non-empty)
+    // file, which is how compacted disks get written back out.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<DiskMap, Box<dyn error::Error>> {
+        let mut spans = vec![];
+
+        let mut lines = reader.lines();
+        let line = lines.next().ok_or("expected input")??;
 
-        let line = reader.lines().next().ok_or("expected input")??;
+        let ids = match lines.next() {
+            Some(ids) if !ids.as_ref().is_ok_and(|ids| ids.trim().is_empty()) => Some(
+                ids?.split_whitespace()
+                    .map(|id| id.parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            _ => None,
+        };
 
         let mut i = 0usize;
         let mut in_free_space = false;
         let mut id = 0u32;
 
-        for ch in line.chars() {
-            let num: u32 = ch
+        for ch in line.trim().chars() {
+            let num = ch
                 .to_digit(10)
-                .expect("expected character to be a valid u32");
-
-            if num != 0 {
-                if in_free_space {
-                    free_space_regions.push(FreeSpaceRegion {
-                        start: i,
-                        size: num,
-                    });
-
-                    let n = i + num as usize;
-                    while i < n {
-                        disk.push(DiskMapEntry::FreeSpace);
-                        i += 1
-                    }
-                } else {
-                    blocks.push(BlockInfo {
-                        start: i,
-                        size: num,
-                    });
-
-                    let n = i + num as usize;
-                    while i < n {
-                        disk.push(DiskMapEntry::FileBlock(id));
-                        i += 1
-                    }
-
-                    id += 1;
+                .ok_or(format!("expected {} to be a digit", ch))? as usize;
+
+            if num != 0 && !in_free_space {
+                spans.push(FileSpan {
+                    id: match &ids {
+                        None => id,
+                        Some(ids) => *ids
+                            .get(spans.len())
+                            .ok_or("expected an id for each file in the disk map")?,
+                    },
+                    start: i,
+                    size: num,
+                });
+
+                id += 1;
+            }
+
+            i += num;
+            in_free_space = !in_free_space;
+        }
+
+        if let Some(ids) = ids {
+            if ids.len() != spans.len() {
+                return Err(format!("expected {} ids, got {}", spans.len(), ids.len()).into());
+            }
+        }
+
+        Ok(DiskMap { spans, size: i })
+    }
+
+    // Writes the disk back out in the same format `from_reader` reads, with a line of ids since files are
+    // probably out of order (or split up) after compacting.
+    pub fn encode(&self) -> String {
+        // Break the disk up into runs of files (with their id) and free space.
+        let mut runs = vec![];
+
+        let mut next_start = 0;
+        for span in self.merged_spans() {
+            if span.start > next_start {
+                runs.push((None, span.start - next_start));
+            }
+
+            runs.push((Some(span.id), span.size));
+            next_start = span.start + span.size;
+        }
+
+        if self.size > next_start {
+            runs.push((None, self.size - next_start));
+        }
+
+        let mut digits = String::new();
+        let mut ids = vec![];
+        let mut in_free_space = false;
+
+        for (id, mut size) in runs {
+            // Digits alternate between files and free space, so two runs of the same kind in a row need an empty
+            // one of the other kind between them.
+            //
+            // Runs that don't fit in a single digit get split up the same way.
+            if id.is_none() != in_free_space {
+                digits.push('0');
+                in_free_space = !in_free_space;
+            }
+
+            loop {
+                let chunk = size.min(9);
+                digits.push_str(&chunk.to_string());
+
+                if let Some(id) = id {
+                    ids.push(id.to_string());
                 }
+
+                size -= chunk;
+                if size == 0 {
+                    break;
+                }
+
+                digits.push('0');
             }
 
             in_free_space = !in_free_space;
         }
 
-        Ok(NewDiskMap {
-            disk_map: DiskMap { disk },
-            blocks,
-            free_space_regions,
-        })
+        format!("{}\n{}\n", digits, ids.join(" "))
+    }
+
+    // Returns the spans in the order they appear on the disk, with neighboring spans of the same file combined.
+    pub fn merged_spans(&self) -> Vec<FileSpan> {
+        let mut merged: Vec<FileSpan> = vec![];
+
+        for span in self.sorted_spans() {
+            match merged.last_mut() {
+                Some(last) if last.id == span.id && last.start + last.size == span.start => {
+                    last.size += span.size;
+                }
+                _ => merged.push(span.clone()),
+            }
+        }
+
+        merged
+    }
+
+    // Returns the spans in the order they appear on the disk.
+    pub fn sorted_spans(&self) -> Vec<&FileSpan> {
+        let mut spans = self.spans.iter().collect::<Vec<_>>();
+        spans.sort_by_key(|span| span.start);
+
+        spans
+    }
+
+    // Figures out where the free space is from the gaps between spans.
+    pub fn free_space_regions(&self) -> Vec<FreeSpaceRegion> {
+        let mut regions = vec![];
+
+        let mut next_start = 0;
+        for span in self.sorted_spans() {
+            if span.start > next_start {
+                regions.push(FreeSpaceRegion {
+                    start: next_start,
+                    size: span.start - next_start,
+                });
+            }
+
+            next_start = span.start + span.size;
+        }
+
+        if self.size > next_start {
+            regions.push(FreeSpaceRegion {
+                start: next_start,
+                size: self.size - next_start,
+            });
+        }
+
+        regions
+    }
+
+    pub fn checksum(&self) -> u64 {
+        // Each span contributes id * (start + (start + 1) + ... + (start + size - 1)), which is just an
+        // arithmetic series, so there's no need to visit each block.
+        self.spans
+            .iter()
+            .map(|span| {
+                let (start, size) = (span.start as u64, span.size as u64);
+
+                span.id as u64 * (size * start + size * (size.saturating_sub(1)) / 2)
+            })
+            .sum()
+    }
+
+    pub fn fragmentation(&self) -> Fragmentation {
+        let mut gaps = 0;
+        let mut file_spans = HashMap::new();
+
+        let mut prev: Option<&FileSpan> = None;
+        for span in self.sorted_spans() {
+            let prev_end = prev.map_or(0, |prev| prev.start + prev.size);
+
+            // If there's free space right before this span, there's a gap between files.
+            if span.start > prev_end {
+                gaps += 1;
+            }
+
+            // Spans of the same file that are right next to each other are really just one span.
+            match prev {
+                Some(prev) if prev.id == span.id && span.start == prev_end => {}
+                _ => *file_spans.entry(span.id).or_insert(0) += 1,
+            }
+
+            prev = Some(span);
+        }
+
+        Fragmentation {
+            gaps,
+            fragmented_files: file_spans.values().filter(|spans| **spans > 1).count(),
+        }
     }
 }
 
+#[derive(Debug)]
+pub struct Fragmentation {
+    // The number of free regions that have a file somewhere after them.
+    pub gaps: usize,
+
+    // The number of files that are split up into more than one span.
+    pub fragmented_files: usize,
+}
+
 impl fmt::Display for DiskMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for entry in self.disk.iter() {
-            match entry {
-                DiskMapEntry::FileBlock(id) => f.write_fmt(format_args!("{}", id))?,
-                DiskMapEntry::FreeSpace => f.write_char('.')?,
+        let mut next_start = 0;
+
+        for span in self.sorted_spans() {
+            for _ in next_start..span.start {
+                f.write_char('.')?;
             }
+
+            for _ in 0..span.size {
+                f.write_fmt(format_args!("{}", span.id))?;
+            }
+
+            next_start = span.start + span.size;
+        }
+
+        for _ in next_start..self.size {
+            f.write_char('.')?;
         }
 
         Ok(())
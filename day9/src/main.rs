@@ -13,6 +13,10 @@ use part_two::part_two;
 
 mod disk_map;
 
+mod compaction;
+
+mod diff;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum DayPart {
     One,
@@ -30,6 +34,21 @@ struct Cli {
 
     #[arg(long = "debug", default_value_t = false)]
     debug: bool,
+
+    #[arg(long = "strategy", value_enum)]
+    strategy: Option<compaction::StrategyKind>,
+
+    #[arg(long = "report", default_value_t = false)]
+    report: bool,
+
+    #[arg(long = "encode", default_value_t = false)]
+    encode: bool,
+
+    #[arg(long = "diff", default_value_t = false)]
+    diff: bool,
+
+    #[arg(long = "width", default_value_t = 64, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    width: usize,
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
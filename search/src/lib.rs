@@ -0,0 +1,572 @@
+// Graph searches that don't care what the graph is.
+//
+// Every search takes a `neighbors` function that, given a state, returns the states you can get to from it (and,
+// for weighted searches, what it costs to get there), so a "graph" can be a grid, a grid plus a heading, or
+// anything else that's hashable.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+// Visits every state reachable from `starts` (in order of how many steps away they are), yielding each one once
+// along with its distance from the closest start.
+pub fn bfs<S, F, I>(starts: impl IntoIterator<Item = S>, neighbors: F) -> Bfs<S, F>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    for start in starts {
+        if seen.insert(start.clone()) {
+            queue.push_back((start, 0));
+        }
+    }
+
+    Bfs {
+        queue,
+        seen,
+        neighbors,
+    }
+}
+
+pub struct Bfs<S, F> {
+    queue: VecDeque<(S, usize)>,
+    seen: HashSet<S>,
+    neighbors: F,
+}
+
+impl<S, F, I> Iterator for Bfs<S, F>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    type Item = (S, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (state, depth) = self.queue.pop_front()?;
+
+        for neighbor in (self.neighbors)(&state) {
+            if self.seen.insert(neighbor.clone()) {
+                self.queue.push_back((neighbor, depth + 1));
+            }
+        }
+
+        Some((state, depth))
+    }
+}
+
+// Visits every state reachable from `starts`, going as deep as possible before backing up, yielding each one
+// once.
+pub fn dfs<S, F, I>(starts: impl IntoIterator<Item = S>, neighbors: F) -> Dfs<S, F>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut stack = starts.into_iter().collect::<Vec<_>>();
+
+    // We take states off of the end, so flip the starts around to go through them in order.
+    stack.reverse();
+
+    Dfs {
+        stack,
+        seen: HashSet::new(),
+        neighbors,
+    }
+}
+
+pub struct Dfs<S, F> {
+    stack: Vec<S>,
+    seen: HashSet<S>,
+    neighbors: F,
+}
+
+impl<S, F, I> Iterator for Dfs<S, F>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    type Item = S;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let state = self.stack.pop()?;
+
+            // A state can end up on the stack more than once before we get to it.
+            if !self.seen.insert(state.clone()) {
+                continue;
+            }
+
+            let neighbors = (self.neighbors)(&state)
+                .into_iter()
+                .filter(|neighbor| !self.seen.contains(neighbor))
+                .collect::<Vec<_>>();
+
+            self.stack.extend(neighbors.into_iter().rev());
+
+            return Some(state);
+        }
+    }
+}
+
+// Finds every state reachable from `start` (including `start`).
+pub fn flood_fill<S, F, I>(start: S, neighbors: F) -> Vec<S>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    bfs([start], neighbors).map(|(state, _)| state).collect()
+}
+
+// Splits `states` up into groups that can all reach each other, in the order each group's first state shows up.
+//
+// This assumes neighbors go both ways (i.e. if b is a neighbor of a, a is a neighbor of b). If they don't, each
+// state still only ends up in one group: the first one that can reach it.
+pub fn connected_components<S, F, I>(
+    states: impl IntoIterator<Item = S>,
+    mut neighbors: F,
+) -> Vec<Vec<S>>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut components = vec![];
+    let mut assigned = HashSet::new();
+
+    for state in states {
+        if assigned.contains(&state) {
+            continue;
+        }
+
+        let component = flood_fill(state, |state: &S| {
+            neighbors(state)
+                .into_iter()
+                .filter(|neighbor| !assigned.contains(neighbor))
+                .collect::<Vec<_>>()
+        });
+        assigned.extend(component.iter().cloned());
+
+        components.push(component);
+    }
+
+    components
+}
+
+// Walks every path from `start` that ends at a goal, yielding each one as the list of states along it.
+//
+// Unlike the other searches, states can show up in more than one path, so this only finishes if there aren't any
+// cycles.
+pub fn paths<S, F, I, G>(start: S, neighbors: F, is_goal: G) -> Paths<S, F, G>
+where
+    S: Clone,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+    G: FnMut(&S) -> bool,
+{
+    Paths {
+        start: Some(start),
+        path: vec![],
+        untried: vec![],
+        neighbors,
+        is_goal,
+    }
+}
+
+pub struct Paths<S, F, G> {
+    start: Option<S>,
+
+    // The path we're currently on, and the neighbors we haven't tried yet at each state along it.
+    path: Vec<S>,
+    untried: Vec<Vec<S>>,
+
+    neighbors: F,
+    is_goal: G,
+}
+
+impl<S, F, G> Paths<S, F, G> {
+    fn push<I>(&mut self, state: S)
+    where
+        F: FnMut(&S) -> I,
+        I: IntoIterator<Item = S>,
+    {
+        let mut untried = (self.neighbors)(&state).into_iter().collect::<Vec<_>>();
+        untried.reverse();
+
+        self.untried.push(untried);
+        self.path.push(state);
+    }
+}
+
+impl<S, F, I, G> Iterator for Paths<S, F, G>
+where
+    S: Clone,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+    G: FnMut(&S) -> bool,
+{
+    type Item = Vec<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(start) = self.start.take() {
+            if (self.is_goal)(&start) {
+                return Some(vec![start]);
+            }
+
+            self.push(start);
+        }
+
+        loop {
+            match self.untried.last_mut()?.pop() {
+                Some(state) if (self.is_goal)(&state) => {
+                    let mut path = self.path.clone();
+                    path.push(state);
+
+                    return Some(path);
+                }
+                Some(state) => self.push(state),
+                None => {
+                    // Nothing left to try from here, so back up a step.
+                    self.untried.pop();
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+// Finds the cheapest path from any of `starts` to a goal, returning the path and what it cost.
+pub fn dijkstra<S, F, I, G>(
+    starts: impl IntoIterator<Item = S>,
+    neighbors: F,
+    is_goal: G,
+) -> Option<(Vec<S>, u64)>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, u64)>,
+    G: FnMut(&S) -> bool,
+{
+    astar(starts, neighbors, |_| 0, is_goal)
+}
+
+// Like `dijkstra`, but checks the states that look closest to a goal first.
+//
+// `heuristic` has to never overestimate how much it'll cost to get to a goal, otherwise the path might not be the
+// cheapest one.
+pub fn astar<S, F, I, H, G>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbors: F,
+    mut heuristic: H,
+    mut is_goal: G,
+) -> Option<(Vec<S>, u64)>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, u64)>,
+    H: FnMut(&S) -> u64,
+    G: FnMut(&S) -> bool,
+{
+    let mut nodes = Nodes::default();
+    let mut queue = BinaryHeap::new();
+
+    for start in starts {
+        let i = nodes.get_or_insert(start);
+        nodes.costs[i] = 0;
+
+        queue.push(Reverse((heuristic(&nodes.states[i]), 0, i)));
+    }
+
+    while let Some(Reverse((_, cost, i))) = queue.pop() {
+        // We already found a cheaper way here.
+        if cost > nodes.costs[i] {
+            continue;
+        }
+
+        if is_goal(&nodes.states[i]) {
+            return Some((nodes.path_to(i), cost));
+        }
+
+        for (neighbor, step_cost) in neighbors(&nodes.states[i].clone()) {
+            // A path that costs more than we can count isn't going to be the cheapest one.
+            let Some(neighbor_cost) = cost.checked_add(step_cost) else {
+                continue;
+            };
+
+            let j = nodes.get_or_insert(neighbor);
+
+            if neighbor_cost < nodes.costs[j] {
+                nodes.costs[j] = neighbor_cost;
+                nodes.parents[j] = Some(i);
+
+                queue.push(Reverse((
+                    neighbor_cost.saturating_add(heuristic(&nodes.states[j])),
+                    neighbor_cost,
+                    j,
+                )));
+            }
+        }
+    }
+
+    None
+}
+
+// Every state a weighted search has run into so far, along with the cheapest way we know to get to it.
+//
+// States are referred to by index so the queue doesn't need them to be orderable.
+struct Nodes<S> {
+    states: Vec<S>,
+    indexes: HashMap<S, usize>,
+    costs: Vec<u64>,
+    parents: Vec<Option<usize>>,
+}
+
+impl<S> Default for Nodes<S> {
+    fn default() -> Self {
+        Nodes {
+            states: vec![],
+            indexes: HashMap::new(),
+            costs: vec![],
+            parents: vec![],
+        }
+    }
+}
+
+impl<S: Clone + Eq + Hash> Nodes<S> {
+    fn get_or_insert(&mut self, state: S) -> usize {
+        if let Some(i) = self.indexes.get(&state) {
+            return *i;
+        }
+
+        self.states.push(state.clone());
+        self.indexes.insert(state, self.states.len() - 1);
+        self.costs.push(u64::MAX);
+        self.parents.push(None);
+
+        self.states.len() - 1
+    }
+
+    fn path_to(&self, mut i: usize) -> Vec<S> {
+        let mut path = vec![self.states[i].clone()];
+
+        while let Some(parent) = self.parents[i] {
+            path.push(self.states[parent].clone());
+            i = parent;
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small maze where # is a wall:
+    //
+    //   S..#
+    //   .#..
+    //   ...G
+    const MAZE: [&str; 3] = ["S..#", ".#..", "...G"];
+
+    fn open_neighbors(&(x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|&(x, y)| {
+                MAZE.get(y as usize)
+                    .and_then(|row| row.as_bytes().get(x as usize))
+                    .is_some_and(|tile| *tile != b'#')
+                    && x >= 0
+                    && y >= 0
+            })
+            .collect()
+    }
+
+    fn weighted_neighbors(state: &(i32, i32)) -> Vec<((i32, i32), u64)> {
+        open_neighbors(state)
+            .into_iter()
+            .map(|neighbor| (neighbor, 1))
+            .collect()
+    }
+
+    #[test]
+    fn bfs_yields_distances_in_order() {
+        let distances = bfs([(0, 0)], open_neighbors).collect::<HashMap<_, _>>();
+
+        assert_eq!(distances.len(), 10);
+        assert_eq!(distances[&(0, 0)], 0);
+        assert_eq!(distances[&(2, 1)], 3);
+        assert_eq!(distances[&(3, 2)], 5);
+
+        let depths = bfs([(0, 0)], open_neighbors)
+            .map(|(_, depth)| depth)
+            .collect::<Vec<_>>();
+        assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn bfs_measures_from_the_closest_start() {
+        let distances = bfs([(0, 0), (3, 2)], open_neighbors).collect::<HashMap<_, _>>();
+
+        assert_eq!(distances[&(3, 2)], 0);
+        assert_eq!(distances[&(3, 1)], 1);
+        assert_eq!(distances[&(0, 2)], 2);
+    }
+
+    #[test]
+    fn dfs_goes_deep_before_wide() {
+        let graph = HashMap::from([(1, vec![2, 5]), (2, vec![3, 4]), (5, vec![6])]);
+        let neighbors = |n: &i32| graph.get(n).cloned().unwrap_or_default();
+
+        assert_eq!(dfs([1], neighbors).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn dfs_visits_shared_states_once() {
+        let graph = HashMap::from([(1, vec![2, 3]), (2, vec![4]), (3, vec![4]), (4, vec![1])]);
+        let neighbors = |n: &i32| graph.get(n).cloned().unwrap_or_default();
+
+        assert_eq!(dfs([1], neighbors).collect::<Vec<_>>(), [1, 2, 4, 3]);
+    }
+
+    #[test]
+    fn flood_fill_finds_everything_reachable() {
+        assert_eq!(flood_fill((0, 0), open_neighbors).len(), 10);
+    }
+
+    #[test]
+    fn connected_components_group_states() {
+        let components = connected_components(0..6, |n: &i32| {
+            // Evens and odds are separate groups.
+            [n - 2, n + 2]
+                .into_iter()
+                .filter(|n| (0..6).contains(n))
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(
+            components[0].iter().copied().collect::<HashSet<_>>(),
+            HashSet::from([0, 2, 4])
+        );
+        assert_eq!(
+            components[1].iter().copied().collect::<HashSet<_>>(),
+            HashSet::from([1, 3, 5])
+        );
+    }
+
+    #[test]
+    fn connected_components_with_one_way_neighbors() {
+        // 2 can get to 1, but not the other way around, so it depends on which state we start from.
+        let graph = HashMap::from([(2, vec![1])]);
+        let neighbors = |n: &i32| graph.get(n).cloned().unwrap_or_default();
+
+        assert_eq!(connected_components([1, 2], neighbors), [vec![1], vec![2]]);
+        assert_eq!(connected_components([2, 1], neighbors), [vec![2, 1]]);
+    }
+
+    #[test]
+    fn paths_finds_every_path() {
+        let graph = HashMap::from([(1, vec![2, 3]), (2, vec![4]), (3, vec![4, 5])]);
+        let neighbors = |n: &i32| graph.get(n).cloned().unwrap_or_default();
+
+        assert_eq!(
+            paths(1, neighbors, |n| *n == 4).collect::<Vec<_>>(),
+            [vec![1, 2, 4], vec![1, 3, 4]]
+        );
+    }
+
+    #[test]
+    fn paths_from_a_goal_is_just_the_goal() {
+        let neighbors = |n: &i32| vec![n + 1];
+
+        assert_eq!(
+            paths(4, neighbors, |n| *n == 4).collect::<Vec<_>>(),
+            [vec![4]]
+        );
+    }
+
+    #[test]
+    fn dijkstra_finds_the_cheapest_path() {
+        let (path, cost) = dijkstra([(0, 0)], weighted_neighbors, |s| *s == (3, 2)).unwrap();
+
+        assert_eq!(cost, 5);
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(3, 2)));
+    }
+
+    #[test]
+    fn dijkstra_prefers_cheap_detours() {
+        // Going straight from 0 to 2 costs 10, but going through 1 only costs 2.
+        let neighbors = |n: &i32| match n {
+            0 => vec![(2, 10), (1, 1)],
+            1 => vec![(2, 1)],
+            _ => vec![],
+        };
+
+        assert_eq!(
+            dijkstra([0], neighbors, |n| *n == 2),
+            Some((vec![0, 1, 2], 2))
+        );
+    }
+
+    #[test]
+    fn dijkstra_starts_from_the_closest_start() {
+        let (path, cost) =
+            dijkstra([(0, 0), (2, 2)], weighted_neighbors, |s| *s == (3, 2)).unwrap();
+
+        assert_eq!(cost, 1);
+        assert_eq!(path, [(2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn dijkstra_gives_up_when_there_is_no_way_there() {
+        assert_eq!(
+            dijkstra([(0, 0)], weighted_neighbors, |s| *s == (3, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn dijkstra_ignores_paths_that_overflow() {
+        let neighbors = |n: &i32| match n {
+            0 => vec![(1, u64::MAX), (2, 1)],
+            1 => vec![(3, 1)],
+            2 => vec![(3, 5)],
+            _ => vec![],
+        };
+
+        assert_eq!(
+            dijkstra([0], neighbors, |n| *n == 3),
+            Some((vec![0, 2, 3], 6))
+        );
+    }
+
+    #[test]
+    fn astar_matches_dijkstra() {
+        let manhattan = |&(x, y): &(i32, i32)| ((3 - x).abs() + (2 - y).abs()) as u64;
+
+        let (path, cost) =
+            astar([(0, 0)], weighted_neighbors, manhattan, |s| *s == (3, 2)).unwrap();
+        assert_eq!(cost, 5);
+        assert_eq!(path.len(), 6);
+
+        let (path, cost) = astar([(0, 0), (2, 0)], weighted_neighbors, manhattan, |s| {
+            *s == (3, 2)
+        })
+        .unwrap();
+        assert_eq!(cost, 3);
+        assert_eq!(path.first(), Some(&(2, 0)));
+    }
+}